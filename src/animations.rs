@@ -1,7 +1,8 @@
 use ggez::{Context, GameResult};
 use ggez::graphics::{self, Color, DrawMode, DrawParam, Mesh, Rect};
 use std::collections::VecDeque;
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
 #[derive(Debug, Clone)]
 pub struct Particle {
@@ -16,8 +17,7 @@ pub struct Particle {
 }
 
 impl Particle {
-    pub fn new(x: f32, y: f32, color: Color) -> Self {
-        let mut rng = rand::thread_rng();
+    pub fn new<R: Rng>(x: f32, y: f32, color: Color, rng: &mut R) -> Self {
         let angle = rng.gen_range(0.0..std::f32::consts::TAU);
         let speed = rng.gen_range(50.0..150.0);
         
@@ -49,7 +49,7 @@ impl Particle {
             return Ok(());
         }
 
-        let alpha = self.life;
+        let alpha = self.life / self.max_life;
         let mut color = self.color;
         color.a = alpha;
 
@@ -75,7 +75,7 @@ pub struct LineClearAnimation {
 }
 
 impl LineClearAnimation {
-    pub fn new(cleared_lines: &[usize], board_width: usize, block_size: f32, offset_x: f32, offset_y: f32) -> Self {
+    pub fn new<R: Rng>(cleared_lines: &[usize], board_width: usize, block_size: f32, offset_x: f32, offset_y: f32, rng: &mut R) -> Self {
         let mut particles = Vec::new();
         
         for &line_y in cleared_lines {
@@ -86,12 +86,12 @@ impl LineClearAnimation {
                 // Create multiple particles per block for more dramatic effect
                 for _ in 0..3 {
                     let color = Color::new(
-                        0.8 + rand::thread_rng().gen_range(-0.2..0.2),
-                        0.4 + rand::thread_rng().gen_range(-0.2..0.2),
+                        0.8 + rng.gen_range(-0.2..0.2),
+                        0.4 + rng.gen_range(-0.2..0.2),
                         1.0,
                         1.0,
                     );
-                    particles.push(Particle::new(particle_x, particle_y, color));
+                    particles.push(Particle::new(particle_x, particle_y, color, rng));
                 }
             }
         }
@@ -125,17 +125,25 @@ impl LineClearAnimation {
 
 pub struct AnimationManager {
     pub line_clear_animations: VecDeque<LineClearAnimation>,
+    rng: StdRng,
 }
 
 impl AnimationManager {
-    pub fn new() -> Self {
+    pub fn new(seed: u64) -> Self {
         AnimationManager {
             line_clear_animations: VecDeque::new(),
+            rng: StdRng::seed_from_u64(seed),
         }
     }
 
+    // Particles follow the game seed so replays look the same too
+    pub fn reset(&mut self, seed: u64) {
+        self.line_clear_animations.clear();
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
     pub fn add_line_clear_animation(&mut self, cleared_lines: &[usize], board_width: usize, block_size: f32, offset_x: f32, offset_y: f32) {
-        let animation = LineClearAnimation::new(cleared_lines, board_width, block_size, offset_x, offset_y);
+        let animation = LineClearAnimation::new(cleared_lines, board_width, block_size, offset_x, offset_y, &mut self.rng);
         self.line_clear_animations.push_back(animation);
     }
 
@@ -155,8 +163,4 @@ impl AnimationManager {
         }
         Ok(())
     }
-
-    pub fn is_animating(&self) -> bool {
-        !self.line_clear_animations.is_empty()
-    }
 }
@@ -1,32 +1,152 @@
-use ggez::graphics::{self, Color, DrawMode, DrawParam, Mesh, Rect};
-use ggez::{Context, GameResult};
+use rand::Rng;
+
+use crate::cycle::cycle;
 use crate::tetromino::Tetromino;
-use crate::config::{GRID_COLOR, BORDER_COLOR, GHOST_COLOR};
+use crate::config::{Rgba, BOARD_WIDTH, BOARD_HEIGHT, BUFFER_ROWS, GARBAGE_COLOR};
+
+// Visible playfield dimensions in cells, chosen per game. Every board also
+// has BUFFER_ROWS hidden rows above the visible ones.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoardSize {
+    pub width: usize,
+    pub height: usize,
+}
+
+impl BoardSize {
+    pub const STANDARD: BoardSize = BoardSize { width: BOARD_WIDTH, height: BOARD_HEIGHT };
+
+    // The standard size, with its hidden buffer, is the usual 10x40 grid
+    pub const PRESETS: [BoardSize; 3] = [
+        BoardSize::STANDARD,
+        BoardSize { width: 4, height: 20 },
+        BoardSize { width: 20, height: 20 },
+    ];
+
+    pub fn name(&self) -> String {
+        format!("{}x{}", self.width, self.height)
+    }
+
+    pub fn next(self) -> BoardSize {
+        cycle(&Self::PRESETS, self, true)
+    }
+
+    pub fn previous(self) -> BoardSize {
+        cycle(&Self::PRESETS, self, false)
+    }
+}
+
+// What happens to the stack once full rows are removed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineGravity {
+    // Every row above a cleared one moves down as a whole
+    Naive,
+    // Groups of connected cells fall on their own until they land, which can
+    // fill more rows and set off chain clears
+    Cascade,
+}
+
+impl LineGravity {
+    pub const ALL: [LineGravity; 2] = [LineGravity::Naive, LineGravity::Cascade];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LineGravity::Naive => "NAIVE",
+            LineGravity::Cascade => "CASCADE",
+        }
+    }
+
+    pub fn next(self) -> LineGravity {
+        cycle(&Self::ALL, self, true)
+    }
+
+    pub fn previous(self) -> LineGravity {
+        cycle(&Self::ALL, self, false)
+    }
+}
+
+// Where a filled cell came from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CellKind {
+    // Part of a locked piece
+    Piece,
+    // Part of a garbage row the game added
+    Garbage,
+}
+
+// A filled board cell
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cell {
+    pub color: Rgba,
+    pub kind: CellKind,
+    // Game time, in seconds, when the cell was filled
+    pub placed_at: f32,
+}
+
+impl Cell {
+    pub fn piece(color: Rgba, placed_at: f32) -> Self {
+        Cell { color, kind: CellKind::Piece, placed_at }
+    }
 
-pub const BOARD_WIDTH: usize = 10;
-pub const BOARD_HEIGHT: usize = 15;
+    pub fn garbage() -> Self {
+        Cell { color: GARBAGE_COLOR, kind: CellKind::Garbage, placed_at: 0.0 }
+    }
 
+    pub fn is_garbage(&self) -> bool {
+        self.kind == CellKind::Garbage
+    }
+}
+
+// Row 0 is the top of the hidden buffer; the visible field starts at row
+// `buffer_rows` and spans `height` rows
 #[derive(Debug, Clone)]
 pub struct Board {
-    pub grid: Vec<Vec<Option<Color>>>,
-    pub block_size: f32,
-    pub offset_x: f32,
-    pub offset_y: f32,
+    pub grid: Vec<Vec<Option<Cell>>>,
+    pub width: usize,
+    pub height: usize,
+    pub buffer_rows: usize,
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Self::new(BoardSize::STANDARD)
+    }
 }
 
 impl Board {
-    pub fn new(block_size: f32, offset_x: f32, offset_y: f32) -> Self {
-        let grid = vec![vec![None; BOARD_WIDTH]; BOARD_HEIGHT];
+    pub fn new(size: BoardSize) -> Self {
+        let grid = vec![vec![None; size.width]; size.height + BUFFER_ROWS];
         Board {
             grid,
-            block_size,
-            offset_x,
-            offset_y,
+            width: size.width,
+            height: size.height,
+            buffer_rows: BUFFER_ROWS,
         }
     }
 
+    pub fn size(&self) -> BoardSize {
+        BoardSize { width: self.width, height: self.height }
+    }
+
+    // Total rows, hidden buffer included
+    pub fn rows(&self) -> usize {
+        self.buffer_rows + self.height
+    }
+
+    pub fn is_hidden_row(&self, y: i32) -> bool {
+        y < self.buffer_rows as i32
+    }
+
     pub fn clear(&mut self) {
-        self.grid = vec![vec![None; BOARD_WIDTH]; BOARD_HEIGHT];
+        self.grid = vec![vec![None; self.width]; self.rows()];
+    }
+
+    // Empties the hidden buffer and the top `rows` visible rows. Cells below
+    // stay where they are.
+    pub fn clear_top(&mut self, rows: usize) {
+        let end = (self.buffer_rows + rows).min(self.rows());
+        for row in &mut self.grid[..end] {
+            row.fill(None);
+        }
     }
 
     pub fn is_valid_position(&self, tetromino: &Tetromino) -> bool {
@@ -37,8 +157,8 @@ impl Board {
                     let board_y = tetromino.y + y as i32;
                     
                     // Check boundaries
-                    if board_x < 0 || board_x >= BOARD_WIDTH as i32 || 
-                       board_y < 0 || board_y >= BOARD_HEIGHT as i32 {
+                    if board_x < 0 || board_x >= self.width as i32 || 
+                       board_y < 0 || board_y >= self.rows() as i32 {
                         return false;
                     }
                     
@@ -52,15 +172,29 @@ impl Board {
         true
     }
 
-    pub fn place_tetromino(&mut self, tetromino: &Tetromino) {
+    // The first of the tetromino's blocks, in reading order, that overlaps
+    // the stack or leaves the board, as a column and row of its matrix
+    pub fn first_collision(&self, tetromino: &Tetromino) -> Option<(usize, usize)> {
+        for (y, row) in tetromino.blocks.iter().enumerate() {
+            for (x, &block) in row.iter().enumerate() {
+                if block && self.is_occupied(tetromino.x + x as i32, tetromino.y + y as i32) {
+                    return Some((x, y));
+                }
+            }
+        }
+        None
+    }
+
+    // `time` is the game time the cells are stamped with
+    pub fn place_tetromino(&mut self, tetromino: &Tetromino, time: f32) {
         let color = tetromino.get_color();
         for (y, row) in tetromino.blocks.iter().enumerate() {
             for (x, &block) in row.iter().enumerate() {
                 if block {
                     let board_x = (tetromino.x + x as i32) as usize;
                     let board_y = (tetromino.y + y as i32) as usize;
-                    if board_y < BOARD_HEIGHT && board_x < BOARD_WIDTH {
-                        self.grid[board_y][board_x] = Some(color);
+                    if board_y < self.rows() && board_x < self.width {
+                        self.grid[board_y][board_x] = Some(Cell::piece(color, time));
                     }
                 }
             }
@@ -68,93 +202,178 @@ impl Board {
     }
 
     pub fn clear_lines(&mut self) -> usize {
-        let mut lines_cleared = 0;
-        let mut y = BOARD_HEIGHT - 1;
-        
-        while y > 0 {
-            if self.is_line_full(y) {
-                self.remove_line(y);
-                lines_cleared += 1;
-            } else {
-                y -= 1;
+        self.clear_lines_with_animation().len()
+    }
+
+    // Clears full rows at once and returns their indices, top to bottom, as
+    // they were before the rows above fell down
+    pub fn clear_lines_with_animation(&mut self) -> Vec<usize> {
+        let rows = self.full_rows();
+        self.remove_rows(&rows);
+        rows
+    }
+
+    // Full rows, top to bottom, left in place until `remove_rows`
+    pub fn full_rows(&self) -> Vec<usize> {
+        (0..self.rows()).filter(|&y| self.is_line_full(y)).collect()
+    }
+
+    // Removes the given rows and drops everything above them. Indices refer
+    // to the board before any of them is removed.
+    pub fn remove_rows(&mut self, rows: &[usize]) {
+        let mut rows = rows.to_vec();
+        rows.sort_unstable();
+        rows.dedup();
+        for &y in rows.iter().rev() {
+            self.grid.remove(y);
+        }
+        for _ in 0..rows.len() {
+            self.grid.insert(0, vec![None; self.width]);
+        }
+    }
+
+    // Removes the given rows with the chosen gravity
+    pub fn collapse_rows(&mut self, rows: &[usize], gravity: LineGravity) {
+        match gravity {
+            LineGravity::Naive => self.remove_rows(rows),
+            LineGravity::Cascade => self.cascade_rows(rows),
+        }
+    }
+
+    // Empties the given rows without moving anything, then lets every group
+    // of orthogonally connected cells left fall until it rests on the floor
+    // or on another group. Groups are found once, so a group falling past
+    // the side of another does not catch on it.
+    pub fn cascade_rows(&mut self, rows: &[usize]) {
+        for &y in rows {
+            self.grid[y].fill(None);
+        }
+        let (mut groups, count) = self.groups();
+        while self.drop_loose_groups(&mut groups, count) {}
+    }
+
+    // Moves every group with room below it down one row, along with its
+    // labels; false when none could
+    fn drop_loose_groups(&mut self, groups: &mut [Vec<Option<usize>>], count: usize) -> bool {
+        let mut falling = vec![true; count];
+        for (y, row) in groups.iter().enumerate() {
+            for (x, &group) in row.iter().enumerate() {
+                let Some(group) = group else {
+                    continue;
+                };
+                let supported = match groups.get(y + 1) {
+                    Some(below) => below[x].is_some_and(|other| other != group),
+                    None => true,
+                };
+                if supported {
+                    falling[group] = false;
+                }
             }
         }
-        
-        lines_cleared
+        if !falling.contains(&true) {
+            return false;
+        }
+
+        // Bottom up, so every cell moves into a free one
+        for y in (0..self.rows() - 1).rev() {
+            let (above, below) = groups.split_at_mut(y + 1);
+            for (x, group) in above[y].iter_mut().enumerate() {
+                if group.is_some_and(|group| falling[group]) {
+                    below[0][x] = group.take();
+                    self.grid[y + 1][x] = self.grid[y][x].take();
+                }
+            }
+        }
+        true
     }
 
-    pub fn clear_lines_with_animation(&mut self) -> Vec<usize> {
-        let mut cleared_lines = Vec::new();
-        let mut y = BOARD_HEIGHT - 1;
-        
-        while y > 0 {
-            if self.is_line_full(y) {
-                cleared_lines.push(y);
-                self.remove_line(y);
-            } else {
-                y -= 1;
+    // Labels each filled cell with its group of orthogonally connected cells,
+    // and returns the labels along with how many groups there are
+    fn groups(&self) -> (Vec<Vec<Option<usize>>>, usize) {
+        let mut groups = vec![vec![None; self.width]; self.rows()];
+        let mut count = 0;
+        for start_y in 0..self.rows() {
+            for start_x in 0..self.width {
+                if self.grid[start_y][start_x].is_none() || groups[start_y][start_x].is_some() {
+                    continue;
+                }
+                groups[start_y][start_x] = Some(count);
+                let mut stack = vec![(start_x, start_y)];
+                while let Some((x, y)) = stack.pop() {
+                    let neighbours = [
+                        (x.wrapping_sub(1), y),
+                        (x + 1, y),
+                        (x, y.wrapping_sub(1)),
+                        (x, y + 1),
+                    ];
+                    for (nx, ny) in neighbours {
+                        if nx < self.width && ny < self.rows() && self.grid[ny][nx].is_some() && groups[ny][nx].is_none() {
+                            groups[ny][nx] = Some(count);
+                            stack.push((nx, ny));
+                        }
+                    }
+                }
+                count += 1;
             }
         }
-        
-        cleared_lines
+        (groups, count)
     }
 
-    fn is_line_full(&self, y: usize) -> bool {
-        self.grid[y].iter().all(|cell| cell.is_some())
+    // Whether the board would be empty once the given rows are removed
+    pub fn is_empty_without(&self, rows: &[usize]) -> bool {
+        self.grid
+            .iter()
+            .enumerate()
+            .all(|(y, row)| rows.contains(&y) || row.iter().all(|cell| cell.is_none()))
     }
 
-    fn remove_line(&mut self, y: usize) {
-        self.grid.remove(y);
-        self.grid.insert(0, vec![None; BOARD_WIDTH]);
-    }
-
-    pub fn draw(&self, ctx: &mut Context, canvas: &mut graphics::Canvas) -> GameResult {
-        // Draw background grid
-        for y in 0..BOARD_HEIGHT {
-            for x in 0..BOARD_WIDTH {
-                let rect = Rect::new(
-                    x as f32 * self.block_size + self.offset_x,
-                    y as f32 * self.block_size + self.offset_y,
-                    self.block_size,
-                    self.block_size,
-                );
-                
-                // Draw grid lines
-                let grid_color = Color::new(GRID_COLOR.0, GRID_COLOR.1, GRID_COLOR.2, GRID_COLOR.3);
-                let grid_mesh = Mesh::new_rectangle(ctx, DrawMode::stroke(1.0), rect, grid_color)?;
-                canvas.draw(&grid_mesh, DrawParam::default());
-                
-                // Draw placed blocks
-                if let Some(color) = self.grid[y][x] {
-                    let block_mesh = Mesh::new_rectangle(ctx, DrawMode::fill(), rect, color)?;
-                    canvas.draw(&block_mesh, DrawParam::default());
-                    
-                    // Draw border for placed blocks
-                    let border_color = Color::new(BORDER_COLOR.0, BORDER_COLOR.1, BORDER_COLOR.2, BORDER_COLOR.3);
-                    let border_mesh = Mesh::new_rectangle(ctx, DrawMode::stroke(2.0), rect, border_color)?;
-                    canvas.draw(&border_mesh, DrawParam::default());
-                }
+    // Fills the bottom `rows` rows with garbage, one hole per row. Each row's
+    // hole moves to a new column with probability `messiness` (0 stacks all
+    // holes in one column, 1 moves it every row). The top visible row is
+    // always left free.
+    pub fn add_garbage<R: Rng>(&mut self, rows: usize, messiness: f32, rng: &mut R) {
+        let rows = rows.min(self.height.saturating_sub(1));
+        let mut hole = rng.gen_range(0..self.width);
+        for y in (self.rows() - rows..self.rows()).rev() {
+            if self.width > 1 && rng.gen::<f32>() < messiness {
+                // Any other column
+                hole = (hole + rng.gen_range(1..self.width)) % self.width;
             }
+            self.grid[y] = (0..self.width)
+                .map(|x| if x == hole { None } else { Some(Cell::garbage()) })
+                .collect();
         }
-        
-        // Draw board border
-        let border_rect = Rect::new(
-            self.offset_x - 2.0,
-            self.offset_y - 2.0,
-            BOARD_WIDTH as f32 * self.block_size + 4.0,
-            BOARD_HEIGHT as f32 * self.block_size + 4.0,
-        );
-        let border_color = Color::new(BORDER_COLOR.0, BORDER_COLOR.1, BORDER_COLOR.2, BORDER_COLOR.3);
-        let border_mesh = Mesh::new_rectangle(ctx, DrawMode::stroke(4.0), border_rect, border_color)?;
-        canvas.draw(&border_mesh, DrawParam::default());
-        
-        Ok(())
+    }
+
+    // Rows holding any garbage, leaving out the given rows
+    pub fn garbage_rows_without(&self, rows: &[usize]) -> usize {
+        self.grid
+            .iter()
+            .enumerate()
+            .filter(|(y, row)| !rows.contains(y) && row.iter().flatten().any(Cell::is_garbage))
+            .count()
+    }
+
+    // Cells outside the board count as occupied
+    pub fn is_occupied(&self, x: i32, y: i32) -> bool {
+        if x < 0 || x >= self.width as i32 || y < 0 || y >= self.rows() as i32 {
+            return true;
+        }
+        self.grid[y as usize][x as usize].is_some()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.grid.iter().all(|row| row.iter().all(|cell| cell.is_none()))
+    }
+
+    fn is_line_full(&self, y: usize) -> bool {
+        self.grid[y].iter().all(|cell| cell.is_some())
     }
 
     pub fn get_ghost_position(&self, tetromino: &Tetromino) -> (i32, i32) {
         let mut ghost_y = tetromino.y;
         
-        while ghost_y < BOARD_HEIGHT as i32 {
+        while ghost_y < self.rows() as i32 {
             let mut test_tetromino = tetromino.clone();
             test_tetromino.y = ghost_y + 1;
             
@@ -166,27 +385,83 @@ impl Board {
         
         (tetromino.x, ghost_y)
     }
+}
 
-    pub fn draw_ghost(&self, ctx: &mut Context, canvas: &mut graphics::Canvas, tetromino: &Tetromino) -> GameResult {
-        let (ghost_x, ghost_y) = self.get_ghost_position(tetromino);
-        
-        for (y, row) in tetromino.blocks.iter().enumerate() {
-            for (x, &block) in row.iter().enumerate() {
-                if block {
-                    let rect = Rect::new(
-                        (ghost_x + x as i32) as f32 * self.block_size + self.offset_x,
-                        (ghost_y + y as i32) as f32 * self.block_size + self.offset_y,
-                        self.block_size,
-                        self.block_size,
-                    );
-                    
-                    let ghost_color = Color::new(GHOST_COLOR.0, GHOST_COLOR.1, GHOST_COLOR.2, GHOST_COLOR.3);
-                    let mesh = Mesh::new_rectangle(ctx, DrawMode::stroke(2.0), rect, ghost_color)?;
-                    canvas.draw(&mesh, DrawParam::default());
-                }
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    // A standard board whose bottom rows are given top to bottom, '#' for a block
+    fn board(rows: &[&str]) -> Board {
+        let mut board = Board::default();
+        let top = board.rows() - rows.len();
+        for (y, row) in rows.iter().enumerate() {
+            board.grid[top + y] = row.chars().map(|c| (c == '#').then(Cell::garbage)).collect();
         }
-        
-        Ok(())
+        board
+    }
+
+    fn bottom(board: &Board, rows: usize) -> Vec<String> {
+        board.grid[board.rows() - rows..]
+            .iter()
+            .map(|row| row.iter().map(|cell| if cell.is_some() { '#' } else { '.' }).collect())
+            .collect()
+    }
+
+    #[test]
+    fn naive_gravity_moves_whole_rows() {
+        let mut board = board(&[".........#", "##########", "#########."]);
+        let full = board.full_rows();
+        assert_eq!(full, [board.rows() - 2]);
+        board.collapse_rows(&full, LineGravity::Naive);
+        assert_eq!(bottom(&board, 3), ["..........", ".........#", "#########."]);
+    }
+
+    #[test]
+    fn cascade_drops_loose_groups_until_they_land() {
+        let mut board = board(&["##........", ".#....##..", ".#.....#..", "##########", "#.#....#.."]);
+        board.collapse_rows(&board.full_rows(), LineGravity::Cascade);
+        // The left group sticks to the blocks it lands next to; the right one
+        // lands on the block below it
+        assert_eq!(
+            bottom(&board, 5),
+            ["..........", "..........", "##....##..", ".#.....#..", "###....#.."],
+        );
+    }
+
+    #[test]
+    fn cascade_can_fill_new_rows() {
+        let mut board = board(&[".........#", "##########", "#########.", "#########."]);
+        board.collapse_rows(&board.full_rows(), LineGravity::Cascade);
+        assert_eq!(bottom(&board, 3), ["..........", "#########.", "##########"]);
+    }
+
+    #[test]
+    fn garbage_leaves_one_hole_per_row() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut board = Board::default();
+        board.add_garbage(8, 0.0, &mut rng);
+        let holes: Vec<usize> = board.grid[board.rows() - 8..]
+            .iter()
+            .map(|row| row.iter().position(|cell| cell.is_none()).unwrap())
+            .collect();
+        assert!(holes.iter().all(|&hole| hole == holes[0]));
+        assert_eq!(board.garbage_rows_without(&[]), 8);
+
+        // Never reaching the top visible row
+        let mut board = Board::default();
+        board.add_garbage(100, 1.0, &mut rng);
+        assert_eq!(board.garbage_rows_without(&[]), BOARD_HEIGHT - 1);
+    }
+
+    #[test]
+    fn clear_top_keeps_the_rows_below() {
+        let mut board = board(&["#.........", "##########"]);
+        board.grid[0][0] = Some(Cell::garbage());
+        board.clear_top(board.height - 1);
+        assert!(board.grid[0][0].is_none());
+        assert_eq!(bottom(&board, 2), ["..........", "##########"]);
     }
 }
@@ -1,14 +1,32 @@
+// Cor RGBA usada pelo núcleo do jogo (convertida para ggez::graphics::Color no frontend)
+pub type Rgba = (f32, f32, f32, f32);
+
 // Configurações do jogo
 pub const WINDOW_WIDTH: f32 = 800.0;
 pub const WINDOW_HEIGHT: f32 = 600.0;
 pub const BLOCK_SIZE: f32 = 25.0;
 pub const BOARD_OFFSET_X: f32 = 50.0;
 pub const BOARD_OFFSET_Y: f32 = 50.0;
+pub const HUD_X: f32 = 550.0;
 
 // Configurações de velocidade
+// A gravidade é medida em linhas por quadro, a 60 quadros por segundo (20G = queda instantânea)
+pub const FRAMES_PER_SECOND: f32 = 60.0;
+// Gravidade a partir da qual a peça cai até o fundo assim que aparece (20G)
+pub const INSTANT_GRAVITY: f32 = 20.0;
 pub const INITIAL_DROP_INTERVAL: f32 = 1.0;
 pub const MIN_DROP_INTERVAL: f32 = 0.1;
 pub const LEVEL_SPEED_INCREASE: f32 = 0.1;
+pub const LOCK_DELAY: f32 = 0.5;
+pub const MAX_LOCK_RESETS: u32 = 15;
+// Linhas completas piscam antes de sumir, e há uma espera antes da próxima peça (ARE)
+pub const LINE_CLEAR_DELAY: f32 = 0.3;
+pub const ENTRY_DELAY: f32 = 0.1;
+
+// Configurações de movimento (segundos)
+pub const DAS: f32 = 0.167;
+pub const ARR: f32 = 0.033;
+pub const SOFT_DROP_FACTOR: f32 = 20.0;
 
 // Configurações de pontuação
 pub const SCORE_SINGLE_LINE: u32 = 100;
@@ -16,11 +34,24 @@ pub const SCORE_DOUBLE_LINE: u32 = 300;
 pub const SCORE_TRIPLE_LINE: u32 = 500;
 pub const SCORE_TETRIS: u32 = 800;
 pub const SCORE_HARD_DROP_MULTIPLIER: u32 = 2;
+pub const SCORE_SOFT_DROP: u32 = 1;
+pub const SCORE_TSPIN_MINI: u32 = 100;
+pub const SCORE_TSPIN_MINI_SINGLE: u32 = 200;
+pub const SCORE_TSPIN_MINI_DOUBLE: u32 = 400;
+pub const SCORE_TSPIN: u32 = 400;
+pub const SCORE_TSPIN_SINGLE: u32 = 800;
+pub const SCORE_TSPIN_DOUBLE: u32 = 1200;
+pub const SCORE_TSPIN_TRIPLE: u32 = 1600;
+pub const SCORE_COMBO: u32 = 50;
+// Perfect clear: simples, dupla, tripla, destruição, destruição back-to-back
+pub const SCORE_PERFECT_CLEAR: [u32; 5] = [800, 1200, 1800, 2000, 3200];
 
 // Configurações visuais - Tema Roxo
 pub const GRID_COLOR: (f32, f32, f32, f32) = (0.2, 0.1, 0.3, 1.0);
 pub const GHOST_COLOR: (f32, f32, f32, f32) = (0.6, 0.3, 0.8, 0.5);
 pub const BORDER_COLOR: (f32, f32, f32, f32) = (0.8, 0.4, 1.0, 1.0);
+pub const GARBAGE_COLOR: (f32, f32, f32, f32) = (0.45, 0.4, 0.5, 1.0);
+pub const LINE_FLASH_COLOR: (f32, f32, f32, f32) = (1.0, 0.9, 1.0, 0.8);
 pub const BACKGROUND_COLOR: (f32, f32, f32, f32) = (0.1, 0.05, 0.15, 1.0);
 pub const MENU_BACKGROUND_COLOR: (f32, f32, f32, f32) = (0.15, 0.08, 0.25, 1.0);
 pub const MENU_TEXT_COLOR: (f32, f32, f32, f32) = (0.9, 0.7, 1.0, 1.0);
@@ -28,7 +59,38 @@ pub const MENU_HIGHLIGHT_COLOR: (f32, f32, f32, f32) = (1.0, 0.5, 1.0, 1.0);
 
 // Configurações de jogo
 pub const LINES_PER_LEVEL: u32 = 10;
+pub const DEFAULT_PREVIEW_COUNT: usize = 5;
+pub const MAX_PREVIEW_COUNT: usize = 6;
+// Maior ordem de poliominó gerada automaticamente (monominós até hexominós)
+pub const MAX_POLYOMINO_ORDER: usize = 6;
 pub const BOARD_WIDTH: usize = 10;
 pub const BOARD_HEIGHT: usize = 20;
+// Linhas invisíveis acima do tabuleiro, onde as peças nascem
+pub const BUFFER_ROWS: usize = 20;
+
 
 
+// Modos de jogo
+pub const SPRINT_LINES: u32 = 40;
+// Tempo parcial a cada 10 linhas no sprint
+pub const SPRINT_SPLIT_LINES: u32 = 10;
+// Linhas de lixo no início do modo dig e chance do buraco mudar de coluna a
+// cada linha (0 = sempre a mesma coluna, 1 = muda sempre)
+pub const DIG_GARBAGE_ROWS: usize = 10;
+pub const DIG_MESSINESS: f32 = 0.3;
+// Modo invisível: segundos até uma peça travada sumir. Modo neblina: linhas
+// visíveis acima e abaixo da peça ativa.
+pub const INVISIBLE_FADE_TIME: f32 = 1.0;
+pub const FOG_ROWS: i32 = 3;
+// Segundos em que a pilha reaparece depois de uma linha limpa
+pub const STACK_REVEAL_TIME: f32 = 0.6;
+// Modo zen: ao chegar no topo, esvazia o buffer e estas linhas visíveis do
+// topo, descontando a penalidade da pontuação
+pub const ZEN_CLEARED_ROWS: usize = 10;
+pub const ZEN_TOP_OUT_PENALTY: u32 = 1000;
+// Duração padrão do modo ultra, em segundos
+pub const ULTRA_TIME_LIMIT: f32 = 120.0;
+// Aviso nos últimos segundos de uma partida com tempo
+pub const TIME_WARNING: f32 = 10.0;
+// Recordes pessoais, salvos entre sessões
+pub const RECORDS_FILE: &str = "records.json";
@@ -0,0 +1,28 @@
+// Left/Right cycling shared by the menu options: wraps around both ends.
+
+pub fn cycle_index(index: usize, count: usize, forward: bool) -> usize {
+    if forward {
+        (index + 1) % count
+    } else {
+        (index + count - 1) % count
+    }
+}
+
+// Unknown values restart from the first entry
+pub fn cycle<T: PartialEq + Copy>(all: &[T], current: T, forward: bool) -> T {
+    let index = all.iter().position(|&value| value == current).unwrap_or(0);
+    all[cycle_index(index, all.len(), forward)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wraps_around_both_ends() {
+        assert_eq!(cycle_index(2, 3, true), 0);
+        assert_eq!(cycle_index(0, 3, false), 2);
+        assert_eq!(cycle(&['a', 'b', 'c'], 'b', true), 'c');
+        assert_eq!(cycle(&['a', 'b', 'c'], 'a', false), 'c');
+    }
+}
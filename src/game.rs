@@ -1,9 +1,35 @@
-use ggez::{Context, GameResult};
-use ggez::graphics;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
 use crate::tetromino::Tetromino;
-use crate::board::Board;
-use crate::animations::AnimationManager;
+use crate::board::{Board, BoardSize, LineGravity};
+use crate::config::{
+    DEFAULT_PREVIEW_COUNT, MAX_PREVIEW_COUNT, FRAMES_PER_SECOND,
+    SCORE_SOFT_DROP, SCORE_HARD_DROP_MULTIPLIER, ULTRA_TIME_LIMIT, TIME_WARNING,
+    DIG_GARBAGE_ROWS, DIG_MESSINESS, INVISIBLE_FADE_TIME, FOG_ROWS, STACK_REVEAL_TIME, INSTANT_GRAVITY,
+    ZEN_CLEARED_ROWS, ZEN_TOP_OUT_PENALTY,
+};
+use crate::piece_set::{PieceDef, PieceSet};
+use crate::puzzle::Puzzle;
+use crate::randomizer::{Randomizer, RandomizerKind};
+use crate::rotation::KickTable;
+use crate::rules::{LockReset, Master, Modern, RuleSet};
+use crate::input::{Handling, HeldInput, InputState};
+use crate::mode::GameMode;
+use crate::scoring::{ClearAction, ScoreState, TSpin};
+use crate::timing::Delays;
+
+// Guideline top-out rules
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TopOut {
+    // A new piece spawned overlapping the stack
+    BlockOut,
+    // A piece locked entirely inside the hidden buffer rows
+    LockOut,
+}
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum GameState {
@@ -11,242 +37,968 @@ pub enum GameState {
     Playing,
     Paused,
     GameOver,
+    // The mode's goal was reached
+    Finished,
+}
+
+// What a running game is doing: a piece is falling, full rows are flashing
+// before they collapse (line clear delay), or the next piece is about to
+// appear (entry delay, ARE). `chain` counts the cascades since the piece
+// locked, 0 for the rows it filled itself.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Phase {
+    Falling,
+    LineClear { rows: Vec<usize>, timer: f32, chain: u32 },
+    Entry { timer: f32 },
+}
+
+// Player input understood by the game core, independent of any keyboard mapping
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    MoveLeft,
+    MoveRight,
+    SoftDrop,
+    Rotate,
+    RotateLeft,
+    Rotate180,
+    HardDrop,
+    Hold,
+    TogglePause,
+    Reset,
+}
+
+// Things that happened during a tick or command, for frontends to react to
+// (animations, sounds, network messages...)
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameEvent {
+    PiecePlaced,
+    // Rows removed at the end of the line clear delay, as indices on the
+    // board just before they collapsed
+    LinesCleared(Vec<usize>),
+    LevelUp(u32),
+    Scored(ClearAction),
+    // Time taken to reach a split, every few lines in timed modes
+    Split { lines: u32, time: f32 },
+    // The time limit is about to run out
+    TimeWarning,
+    // Zen mode topped out: the top of the stack was cleared and `penalty`
+    // points taken off
+    StackCleared { penalty: u32 },
+    GameOver,
+    Finished,
 }
 
 pub struct Game {
     pub board: Board,
+    pub board_size: BoardSize,
+    pub line_gravity: LineGravity,
     pub current_tetromino: Option<Tetromino>,
-    pub next_tetromino: Tetromino,
+    pub next_queue: VecDeque<Tetromino>,
+    pub preview_count: usize,
+    pub hold_tetromino: Option<Tetromino>,
+    pub hold_used: bool,
     pub score: u32,
     pub level: u32,
     pub lines_cleared: u32,
     pub state: GameState,
-    pub drop_timer: f32,
-    pub drop_interval: f32,
-    pub block_size: f32,
-    pub animations: AnimationManager,
+    pub phase: Phase,
+    pub delays: Delays,
+    pub top_out: Option<TopOut>,
+    // Rows of gravity owed to the falling piece; it moves a row per whole one
+    pub drop_progress: f32,
+    // Rows per frame, from the rule set and level
+    pub gravity: f32,
+    pub lock_timer: f32,
+    pub lock_delay: f32,
+    pub lock_resets: u32,
+    pub lock_reset: LockReset,
+    lowest_y: i32,
+    pub input: InputState,
+    pub handling: Handling,
+    pub scoring: ScoreState,
+    // Set by a successful rotation, cleared by any other movement; used for T-spins
+    last_move_rotation: bool,
+    // Whether that rotation was a quarter turn that used the TST/fin kick
+    last_kick_tst: bool,
+    pub events: Vec<GameEvent>,
+    pub seed: u64,
+    rng: StdRng,
+    pub randomizer_kind: RandomizerKind,
+    randomizer: Box<dyn Randomizer>,
+    // Pieces in play: the chosen set, or its version for the rotation system,
+    // without those too wide for the board
+    pub piece_set: PieceSet,
+    // Set chosen with `set_piece_set`
+    base_piece_set: PieceSet,
+    // Rules in play: the chosen ones, or a mode's wrapper around them
+    pub rules: Rc<dyn RuleSet>,
+    // Rules chosen with `set_rules`
+    base_rules: Rc<dyn RuleSet>,
+    pub mode: GameMode,
+    // Seconds played, pauses excluded
+    pub elapsed: f32,
+    pub splits: Vec<f32>,
+    // Seconds a timed mode lasts
+    pub time_limit: f32,
+    // Garbage the board starts with in modes that have it
+    pub garbage_rows: usize,
+    pub messiness: f32,
+    // Played in puzzle mode
+    pub puzzle: Option<Puzzle>,
+    // What is left of the puzzle's queue, not yet in `next_queue`
+    puzzle_queue: VecDeque<Rc<PieceDef>>,
+    pub pieces_placed: u32,
+    // Seconds left showing a hidden stack after a line clear
+    reveal_timer: f32,
+    // Top and bottom rows of the last locked piece; the fog stays clear
+    // around them until the next piece appears
+    last_piece_rows: (i32, i32),
+    // Score and lines a zen session starts from
+    carried_score: u32,
+    carried_lines: u32,
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Game {
     pub fn new() -> Self {
-        let block_size = 25.0;
-        let offset_x = 50.0;
-        let offset_y = 50.0;
-        
-        Game {
-            board: Board::new(block_size, offset_x, offset_y),
+        Self::with_seed(Self::random_seed())
+    }
+
+    // Same seed plus the same inputs always gives the same game
+    pub fn with_seed(seed: u64) -> Self {
+        let piece_set = PieceSet::default();
+        let randomizer_kind = piece_set.randomizer;
+        let rules: Rc<dyn RuleSet> = Rc::new(Modern);
+        let mut game = Game {
+            board: Board::new(BoardSize::STANDARD),
+            board_size: BoardSize::STANDARD,
+            line_gravity: LineGravity::Naive,
             current_tetromino: None,
-            next_tetromino: Tetromino::random(),
+            next_queue: VecDeque::new(),
+            preview_count: DEFAULT_PREVIEW_COUNT,
+            hold_tetromino: None,
+            hold_used: false,
             score: 0,
             level: 1,
             lines_cleared: 0,
             state: GameState::Menu,
-            drop_timer: 0.0,
-            drop_interval: 1.0,
-            block_size,
-            animations: AnimationManager::new(),
+            phase: Phase::Falling,
+            delays: rules.delays(),
+            top_out: None,
+            drop_progress: 0.0,
+            gravity: rules.gravity(1),
+            lock_timer: 0.0,
+            lock_delay: rules.lock_delay(1),
+            lock_resets: 0,
+            lock_reset: rules.lock_reset(),
+            lowest_y: 0,
+            input: InputState::new(),
+            handling: rules.handling(),
+            scoring: ScoreState::new(),
+            last_move_rotation: false,
+            last_kick_tst: false,
+            events: Vec::new(),
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+            randomizer_kind,
+            randomizer: randomizer_kind.create(&piece_set),
+            base_piece_set: piece_set.clone(),
+            piece_set,
+            base_rules: rules.clone(),
+            rules,
+            mode: GameMode::Marathon,
+            elapsed: 0.0,
+            splits: Vec::new(),
+            time_limit: ULTRA_TIME_LIMIT,
+            garbage_rows: DIG_GARBAGE_ROWS,
+            messiness: DIG_MESSINESS,
+            puzzle: None,
+            puzzle_queue: VecDeque::new(),
+            pieces_placed: 0,
+            reveal_timer: 0.0,
+            last_piece_rows: (0, 0),
+            carried_score: 0,
+            carried_lines: 0,
+        };
+        game.fill_queue();
+        game
+    }
+
+    // Seeds are kept short so players can read them off the HUD and type them back
+    pub fn random_seed() -> u64 {
+        rand::thread_rng().gen_range(0..1_000_000_000)
+    }
+
+    // Takes effect on the next start or reset
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    // Takes effect on the next start or reset
+    pub fn set_randomizer(&mut self, kind: RandomizerKind) {
+        self.randomizer_kind = kind;
+    }
+
+    // Takes effect on the next start or reset
+    pub fn set_piece_set(&mut self, set: PieceSet) {
+        self.base_piece_set = set;
+    }
+
+    // Takes effect on the next start or reset
+    pub fn set_board_size(&mut self, size: BoardSize) {
+        self.board_size = size;
+    }
+
+    // Takes effect on the next line clear
+    pub fn set_line_gravity(&mut self, gravity: LineGravity) {
+        self.line_gravity = gravity;
+    }
+
+    // Takes effect on the next start or reset
+    pub fn set_mode(&mut self, mode: GameMode) {
+        self.mode = mode;
+    }
+
+    // Takes effect on the next start or reset
+    pub fn set_time_limit(&mut self, seconds: f32) {
+        self.time_limit = seconds;
+    }
+
+    // Takes effect on the next start or reset. `rows` is kept below the
+    // height of the board size already set, `messiness` is clamped to 0..=1.
+    pub fn set_garbage(&mut self, rows: usize, messiness: f32) {
+        self.garbage_rows = rows.min(self.board_size.height.saturating_sub(1));
+        self.messiness = messiness.clamp(0.0, 1.0);
+    }
+
+    // Takes effect on the next start or reset, in puzzle mode
+    pub fn set_puzzle(&mut self, puzzle: Option<Puzzle>) {
+        self.puzzle = puzzle;
+    }
+
+    // Totals from earlier zen sessions. Takes effect on the next start or
+    // reset, in zen mode.
+    pub fn set_carry_over(&mut self, score: u32, lines: u32) {
+        self.carried_score = score;
+        self.carried_lines = lines;
+    }
+
+    // Takes effect on the next start or reset
+    pub fn set_rules(&mut self, rules: Rc<dyn RuleSet>) {
+        self.base_rules = rules;
+    }
+
+    // Number of upcoming pieces shown, 0 to MAX_PREVIEW_COUNT
+    pub fn set_preview_count(&mut self, count: usize) {
+        self.preview_count = count.min(MAX_PREVIEW_COUNT);
+    }
+
+    // None once a puzzle's queue runs out; the randomizer never does
+    fn next_piece(&mut self) -> Option<Tetromino> {
+        if self.active_puzzle().is_some() {
+            return self.puzzle_queue.pop_front().map(Tetromino::new);
+        }
+        let index = self.randomizer.next(&mut self.rng);
+        Some(Tetromino::new(self.piece_set.pieces[index].clone()))
+    }
+
+    // Top up the queue from the randomizer. Pieces are always drawn in the same
+    // order, so the preview size never changes the sequence for a seed.
+    fn fill_queue(&mut self) {
+        while self.next_queue.len() < self.preview_count {
+            let Some(piece) = self.next_piece() else {
+                break;
+            };
+            self.next_queue.push_back(piece);
         }
     }
 
-    pub fn update(&mut self, ctx: &mut Context) {
+    fn active_puzzle(&self) -> Option<&Puzzle> {
+        match self.mode {
+            GameMode::Puzzle => self.puzzle.as_ref(),
+            _ => None,
+        }
+    }
+
+    // Advance the simulation by `dt` seconds
+    pub fn tick(&mut self, dt: f32) {
         if self.state != GameState::Playing {
             return;
         }
+        self.elapsed += dt;
+        self.reveal_timer = (self.reveal_timer - dt).max(0.0);
+        if self.mode.is_timed() {
+            let remaining = self.time_limit - self.elapsed;
+            if remaining <= 0.0 {
+                self.elapsed = self.time_limit;
+                self.state = GameState::Finished;
+                self.events.push(GameEvent::Finished);
+                return;
+            }
+            if remaining <= TIME_WARNING && remaining + dt > TIME_WARNING {
+                self.events.push(GameEvent::TimeWarning);
+            }
+        }
 
-        let dt = ctx.time.delta().as_secs_f32();
-        self.drop_timer += dt;
+        // Auto repeat for held left/right. DAS keeps charging during the
+        // delays, so the next piece can shift as soon as it appears.
+        let direction = self.input.direction();
+        let steps = self.input.auto_shift_steps(dt, &self.handling, self.board.width as u32);
+        if self.phase != Phase::Falling {
+            self.advance_delays(dt);
+            return;
+        }
+        for _ in 0..steps {
+            if !self.try_shift(direction) {
+                break;
+            }
+            self.on_piece_moved();
+        }
 
-        if self.drop_timer >= self.drop_interval {
-            self.drop_timer = 0.0;
-            self.move_down();
+        // Holding soft drop speeds up gravity. At 20G and above the piece is
+        // already resting on the stack, see `apply_instant_gravity`.
+        self.apply_instant_gravity();
+        let soft_drop = self.input.is_held(HeldInput::SoftDrop);
+        let gravity = if soft_drop {
+            self.gravity * self.handling.soft_drop_factor
+        } else {
+            self.gravity
+        };
+
+        self.drop_progress += gravity * dt * FRAMES_PER_SECOND;
+        while self.drop_progress >= 1.0 {
+            self.drop_progress -= 1.0;
+            if !self.step_down() {
+                self.drop_progress = 0.0;
+                break;
+            }
+            if soft_drop {
+                self.score += SCORE_SOFT_DROP;
+            }
+        }
+
+        // Lock delay: a grounded piece locks once the timer runs out, or at
+        // once when it has used up its move/rotate resets
+        if self.is_grounded() {
+            self.lock_timer += dt;
+            let out_of_resets = matches!(self.lock_reset, LockReset::Move { max } if self.lock_resets >= max);
+            if self.lock_timer >= self.lock_delay || out_of_resets {
+                self.place_tetromino();
+            }
+        } else {
+            self.lock_timer = 0.0;
         }
     }
 
-    pub fn draw(&self, ctx: &mut Context, canvas: &mut graphics::Canvas) -> GameResult {
-        // Draw board
-        self.board.draw(ctx, canvas)?;
+    fn advance_delays(&mut self, dt: f32) {
+        match self.phase {
+            Phase::LineClear { ref mut timer, .. } | Phase::Entry { ref mut timer } => *timer += dt,
+            Phase::Falling => {}
+        }
+        self.finish_delays();
+    }
 
-        // Draw current tetromino
-        if let Some(ref tetromino) = self.current_tetromino {
-            // Draw ghost piece
-            self.board.draw_ghost(ctx, canvas, tetromino)?;
-            
-            // Draw current tetromino
-            tetromino.draw(ctx, canvas, self.block_size, self.board.offset_x, self.board.offset_y)?;
+    // Move past delays that have run out; zero delays pass within the same call
+    fn finish_delays(&mut self) {
+        while let Phase::LineClear { ref rows, timer, chain } = self.phase {
+            if timer < self.delays.line_clear(self.level) {
+                break;
+            }
+            let rows = rows.clone();
+            self.board.collapse_rows(&rows, self.line_gravity);
+            self.events.push(GameEvent::LinesCleared(rows));
+            self.reveal_timer = STACK_REVEAL_TIME;
+            self.phase = Phase::Entry { timer: 0.0 };
+            if self.line_gravity == LineGravity::Cascade {
+                self.check_chain(chain + 1);
+            }
+        }
+        if self.state != GameState::Playing {
+            return;
         }
+        if let Phase::Entry { timer } = self.phase {
+            if timer >= self.delays.entry(self.level) {
+                self.spawn_tetromino();
+            }
+        }
+    }
 
-        Ok(())
+    // Rows flashing during the line clear delay, and how far the delay has run
+    // from 0.0 to 1.0
+    pub fn clearing_rows(&self) -> Option<(&[usize], f32)> {
+        match self.phase {
+            Phase::LineClear { ref rows, timer, .. } => {
+                let delay = self.delays.line_clear(self.level);
+                let progress = if delay <= 0.0 { 1.0 } else { (timer / delay).min(1.0) };
+                Some((rows, progress))
+            }
+            _ => None,
+        }
     }
 
-    pub fn spawn_tetromino(&mut self) {
-        self.current_tetromino = Some(self.next_tetromino.clone());
-        self.next_tetromino = Tetromino::random();
-        
-        // Check if game is over
-        if let Some(ref tetromino) = self.current_tetromino {
-            if !self.board.is_valid_position(tetromino) {
-                self.state = GameState::GameOver;
+    // True when the falling piece is resting on the stack or the floor
+    pub fn is_grounded(&self) -> bool {
+        match self.current_tetromino {
+            Some(ref tetromino) => {
+                let mut below = tetromino.clone();
+                below.y += 1;
+                !self.board.is_valid_position(&below)
             }
+            None => false,
         }
     }
 
-    pub fn move_left(&mut self) {
-        if self.state != GameState::Playing {
+    // How far the lock delay has run, from 0.0 to 1.0
+    pub fn lock_progress(&self) -> f32 {
+        if self.lock_delay <= 0.0 {
+            return 0.0;
+        }
+        (self.lock_timer / self.lock_delay).min(1.0)
+    }
+
+    pub fn apply(&mut self, command: Command) {
+        match command {
+            Command::MoveLeft => self.move_left(),
+            Command::MoveRight => self.move_right(),
+            Command::SoftDrop => self.soft_drop(),
+            Command::Rotate => self.rotate(),
+            Command::RotateLeft => self.rotate_left(),
+            Command::Rotate180 => self.rotate_180(),
+            Command::HardDrop => self.hard_drop(),
+            Command::Hold => self.hold(),
+            Command::TogglePause => self.toggle_pause(),
+            Command::Reset => self.reset(),
+        }
+    }
+
+    // Start holding an input. Acts once right away, then auto-repeats from `tick`.
+    pub fn press(&mut self, input: HeldInput) {
+        self.input.press(input);
+        match input {
+            HeldInput::Left => self.move_left(),
+            HeldInput::Right => self.move_right(),
+            HeldInput::SoftDrop => self.soft_drop(),
+        }
+    }
+
+    pub fn release(&mut self, input: HeldInput) {
+        self.input.release(input);
+    }
+
+    // Take all events produced since the last call
+    pub fn drain_events(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    pub fn spawn_tetromino(&mut self) {
+        // A puzzle whose queue is used up still plays its held piece
+        let next = self
+            .next_queue
+            .pop_front()
+            .or_else(|| self.next_piece())
+            .or_else(|| self.hold_tetromino.take());
+        let Some(mut tetromino) = next else {
+            self.fail_objective();
             return;
+        };
+        self.move_to_spawn(&mut tetromino);
+        self.current_tetromino = Some(tetromino);
+        self.phase = Phase::Falling;
+        self.fill_queue();
+        self.check_block_out();
+        self.reset_lock_delay();
+        self.apply_instant_gravity();
+        self.last_move_rotation = false;
+    }
+
+    // Pieces spawn in the two hidden rows just above the visible field, in
+    // their set's spawn column (or centered) and moved by their own spawn
+    // offset, then drop one row at once if nothing is in the way
+    fn move_to_spawn(&self, tetromino: &mut Tetromino) {
+        let piece_width = tetromino.blocks.first().map_or(0, |row| row.len()) as i32;
+        let (offset_x, offset_y) = tetromino.piece.spawn_offset;
+        tetromino.x = self.piece_set.spawn_x(piece_width, self.board.width as i32) + offset_x;
+        tetromino.y = (self.board.buffer_rows as i32 - 2).max(0) + offset_y;
+
+        tetromino.y += 1;
+        if !self.board.is_valid_position(tetromino) {
+            tetromino.y -= 1;
         }
+    }
 
-        if let Some(ref mut tetromino) = self.current_tetromino {
-            tetromino.x -= 1;
+    fn check_block_out(&mut self) {
+        if let Some(ref tetromino) = self.current_tetromino {
             if !self.board.is_valid_position(tetromino) {
-                tetromino.x += 1;
+                self.game_over(TopOut::BlockOut);
             }
         }
     }
 
-    pub fn move_right(&mut self) {
-        if self.state != GameState::Playing {
+    fn game_over(&mut self, reason: TopOut) {
+        if self.mode == GameMode::Zen {
+            self.clear_stack_top();
             return;
         }
+        self.state = GameState::GameOver;
+        self.top_out = Some(reason);
+        self.events.push(GameEvent::GameOver);
+    }
 
-        if let Some(ref mut tetromino) = self.current_tetromino {
-            tetromino.x += 1;
-            if !self.board.is_valid_position(tetromino) {
-                tetromino.x -= 1;
-            }
+    // Zen's answer to a top out: make room at the top and take the penalty
+    fn clear_stack_top(&mut self) {
+        self.board.clear_top(ZEN_CLEARED_ROWS);
+        let penalty = ZEN_TOP_OUT_PENALTY.min(self.score);
+        self.score -= penalty;
+        self.events.push(GameEvent::StackCleared { penalty });
+    }
+
+    // Ends a puzzle that can no longer be solved, with no top out
+    fn fail_objective(&mut self) {
+        self.state = GameState::GameOver;
+        self.top_out = None;
+        self.events.push(GameEvent::GameOver);
+    }
+
+    pub fn move_left(&mut self) {
+        if self.try_shift(-1) {
+            self.on_piece_moved();
+        }
+    }
+
+    pub fn move_right(&mut self) {
+        if self.try_shift(1) {
+            self.on_piece_moved();
         }
     }
 
+    // Moves one row down. A piece that cannot fall is left to the lock delay.
     pub fn move_down(&mut self) {
+        self.step_down();
+    }
+
+    // Player-driven move down, worth a point per row
+    pub fn soft_drop(&mut self) {
+        if self.step_down() {
+            self.score += SCORE_SOFT_DROP;
+        }
+    }
+
+    fn step_down(&mut self) -> bool {
         if self.state != GameState::Playing {
-            return;
+            return false;
         }
 
         if let Some(ref mut tetromino) = self.current_tetromino {
             tetromino.y += 1;
             if !self.board.is_valid_position(tetromino) {
                 tetromino.y -= 1;
-                self.place_tetromino();
+                return false;
             }
+            if tetromino.y > self.lowest_y {
+                // Reaching a new lowest row restarts the lock delay and gives
+                // the piece its resets back. At 20G the piece is never in the
+                // air during a tick, so this is the only place that happens.
+                self.lowest_y = tetromino.y;
+                self.lock_timer = 0.0;
+                self.lock_resets = 0;
+            }
+            self.last_move_rotation = false;
+            true
         } else {
-            self.spawn_tetromino();
+            false
+        }
+    }
+
+    fn try_shift(&mut self, dx: i32) -> bool {
+        if self.state != GameState::Playing {
+            return false;
+        }
+
+        if let Some(ref mut tetromino) = self.current_tetromino {
+            tetromino.x += dx;
+            if self.board.is_valid_position(tetromino) {
+                self.last_move_rotation = false;
+                return true;
+            }
+            tetromino.x -= dx;
         }
+        false
     }
 
+    // A successful move or rotation restarts the lock delay, up to the reset
+    // limit, when the rule set allows move resets
+    fn on_piece_moved(&mut self) {
+        if let LockReset::Move { max } = self.lock_reset {
+            if self.lock_timer > 0.0 && self.lock_resets < max {
+                self.lock_timer = 0.0;
+                self.lock_resets += 1;
+            }
+        }
+        self.apply_instant_gravity();
+    }
+
+    // 20G: the piece never hangs in the air. It drops onto the stack as it
+    // spawns and after every move or rotation, so it cannot slide over holes.
+    fn apply_instant_gravity(&mut self) {
+        if self.gravity >= INSTANT_GRAVITY {
+            while self.step_down() {}
+        }
+    }
+
+    fn reset_lock_delay(&mut self) {
+        self.lock_timer = 0.0;
+        self.lock_resets = 0;
+        self.lowest_y = self.current_tetromino.as_ref().map_or(0, |tetromino| tetromino.y);
+    }
+
+    // Clockwise
     pub fn rotate(&mut self) {
+        self.rotate_by(1);
+    }
+
+    // Counter-clockwise
+    pub fn rotate_left(&mut self) {
+        self.rotate_by(3);
+    }
+
+    pub fn rotate_180(&mut self) {
+        self.rotate_by(2);
+    }
+
+    // Rotate by a number of clockwise quarter turns
+    fn rotate_by(&mut self, turns: usize) {
         if self.state != GameState::Playing {
             return;
         }
 
         if let Some(ref mut tetromino) = self.current_tetromino {
-            tetromino.rotate();
-            if !self.board.is_valid_position(tetromino) {
-                // Try wall kicks
-                let original_x = tetromino.x;
-                
-                // Try moving left
-                tetromino.x -= 1;
-                if !self.board.is_valid_position(tetromino) {
-                    tetromino.x = original_x + 1;
-                    if !self.board.is_valid_position(tetromino) {
-                        tetromino.x = original_x;
-                        tetromino.rotate();
-                        tetromino.rotate();
-                        tetromino.rotate(); // Rotate back 3 times to get original rotation
-                    }
+            let mut rotated = tetromino.clone();
+            rotated.set_rotation(tetromino.rotation + turns);
+
+            // ARS center column rule: a J, L or T whose rotation is blocked
+            // in the middle column of its box first does not kick
+            let center_blocked = tetromino.piece.kicks == KickTable::ArsCenter
+                && matches!(self.board.first_collision(&rotated), Some((1, _)));
+
+            // Try each kick offset in order, keep the first that fits
+            let kicks = self.rules.rotation().kicks(tetromino.piece.kicks, tetromino.rotation, rotated.rotation);
+            let kicks = if center_blocked { &kicks[..1] } else { kicks };
+            for (kick, &(dx, dy)) in kicks.iter().enumerate() {
+                let mut candidate = rotated.clone();
+                candidate.x += dx;
+                candidate.y -= dy; // Kick tables use +y up, the board uses +y down
+                if self.board.is_valid_position(&candidate) {
+                    *tetromino = candidate;
+                    self.last_move_rotation = true;
+                    // The fifth quarter turn kick is the TST/fin one; 180
+                    // degree tables have no such kick
+                    self.last_kick_tst = turns != 2 && kick == 4;
+                    self.on_piece_moved();
+                    return;
                 }
             }
         }
     }
 
     pub fn hard_drop(&mut self) {
-        if self.state != GameState::Playing {
+        if self.state != GameState::Playing || !self.rules.hard_drop_enabled() {
             return;
         }
 
         if let Some(ref mut tetromino) = self.current_tetromino {
             let mut drop_distance = 0;
-            while self.board.is_valid_position(tetromino) {
+            loop {
                 tetromino.y += 1;
+                if !self.board.is_valid_position(tetromino) {
+                    tetromino.y -= 1;
+                    break;
+                }
                 drop_distance += 1;
             }
-            tetromino.y -= 1;
             
             // Add score for hard drop
-            self.score += drop_distance * 2;
+            self.score += drop_distance * SCORE_HARD_DROP_MULTIPLIER;
+            if drop_distance > 0 {
+                self.last_move_rotation = false;
+            }
             
             self.place_tetromino();
         }
     }
 
+    // Swap the falling piece with the hold slot, at most once per drop
+    pub fn hold(&mut self) {
+        if self.state != GameState::Playing || self.hold_used || !self.rules.hold_enabled() {
+            return;
+        }
+
+        if let Some(current) = self.current_tetromino.take() {
+            // Held pieces always come back in spawn orientation and position
+            let held = Tetromino::new(current.piece);
+            match self.hold_tetromino.replace(held) {
+                Some(previous) => {
+                    let mut tetromino = Tetromino::new(previous.piece);
+                    self.move_to_spawn(&mut tetromino);
+                    self.current_tetromino = Some(tetromino);
+                    self.check_block_out();
+                    self.reset_lock_delay();
+                    self.apply_instant_gravity();
+                    self.last_move_rotation = false;
+                }
+                None => self.spawn_tetromino(),
+            }
+            self.hold_used = true;
+        }
+    }
+
     fn place_tetromino(&mut self) {
         if let Some(tetromino) = self.current_tetromino.take() {
-            self.board.place_tetromino(&tetromino);
+            // T-spins are judged on the board before the piece is added
+            let t_spin = self.detect_t_spin(&tetromino);
+            let locked_in_buffer = self.is_above_visible_field(&tetromino);
+            self.last_piece_rows = piece_rows(&tetromino);
+            self.board.place_tetromino(&tetromino, self.elapsed);
+            self.hold_used = false;
+            self.last_move_rotation = false;
+            self.pieces_placed += 1;
+            self.events.push(GameEvent::PiecePlaced);
             
-            // Clear lines and update score
-            let cleared_lines = self.board.clear_lines_with_animation();
-            if !cleared_lines.is_empty() {
-                self.lines_cleared += cleared_lines.len() as u32;
-                self.update_score(cleared_lines.len());
-                self.update_level();
-                
-                // Add line clear animation
-                self.animations.add_line_clear_animation(
-                    &cleared_lines,
-                    crate::board::BOARD_WIDTH,
-                    self.block_size,
-                    self.board.offset_x,
-                    self.board.offset_y,
-                );
+            // Score full rows now; they stay on the board until the line
+            // clear delay is over
+            let full_rows = self.board.full_rows();
+            if let Some(solved) = self.score_rows(&full_rows, t_spin, 0) {
+                self.end_objective(solved, full_rows);
+                return;
             }
             
-            // Spawn next tetromino
-            self.spawn_tetromino();
+            if locked_in_buffer {
+                self.game_over(TopOut::LockOut);
+                if self.state == GameState::GameOver {
+                    return;
+                }
+            }
+            
+            // Next tetromino spawns once the delays are over
+            self.phase = if full_rows.is_empty() {
+                Phase::Entry { timer: 0.0 }
+            } else {
+                Phase::LineClear { rows: full_rows, timer: 0.0, chain: 0 }
+            };
+            self.finish_delays();
+        }
+    }
+
+    // Scores `full_rows`, filled by the locked piece (chain 0) or by a
+    // cascade after it, and returns the mode's verdict. Each step of a chain
+    // multiplies its points: x2, x3 and so on. Chain steps are no new piece,
+    // so they neither extend the combo nor break back-to-back.
+    fn score_rows(&mut self, full_rows: &[usize], t_spin: TSpin, chain: u32) -> Option<bool> {
+        let perfect_clear = !full_rows.is_empty() && self.board.is_empty_without(full_rows);
+        let mut chain_state = ScoreState::new();
+        let state = if chain == 0 { &mut self.scoring } else { &mut chain_state };
+        let mut action = self.rules.score(state, full_rows.len(), t_spin, perfect_clear, self.level);
+        action.chain = chain;
+        action.points *= chain + 1;
+        self.score += action.points;
+        if !full_rows.is_empty() {
+            let lines_before = self.lines_cleared;
+            self.lines_cleared += full_rows.len() as u32;
+            self.record_splits(lines_before);
+            self.update_level();
+        }
+        let result = self.objective_result(full_rows, &action);
+        if action.is_notable() {
+            self.events.push(GameEvent::Scored(action));
         }
+        result
     }
 
-    fn update_score(&mut self, lines_cleared: usize) {
-        let points = match lines_cleared {
-            1 => 100,
-            2 => 300,
-            3 => 500,
-            4 => 800,
-            _ => 0,
+    // Rows filled by a cascade start the next step of the chain
+    fn check_chain(&mut self, chain: u32) {
+        let full_rows = self.board.full_rows();
+        if full_rows.is_empty() {
+            return;
+        }
+        if let Some(solved) = self.score_rows(&full_rows, TSpin::None, chain) {
+            self.end_objective(solved, full_rows);
+            return;
+        }
+        self.phase = Phase::LineClear { rows: full_rows, timer: 0.0, chain };
+    }
+
+    fn end_objective(&mut self, solved: bool, full_rows: Vec<usize>) {
+        // No next piece to wait for: the last rows go at once
+        if !full_rows.is_empty() {
+            self.board.collapse_rows(&full_rows, self.line_gravity);
+            self.events.push(GameEvent::LinesCleared(full_rows));
+        }
+        if solved {
+            self.state = GameState::Finished;
+            self.events.push(GameEvent::Finished);
+        } else {
+            self.fail_objective();
+        }
+    }
+
+    // Whether the piece just placed, clearing `full_rows`, completes the mode
+    // (Some(true)) or fails it (Some(false))
+    fn objective_result(&self, full_rows: &[usize], action: &ClearAction) -> Option<bool> {
+        if let Some(puzzle) = self.active_puzzle() {
+            return puzzle.objective.check(self.lines_cleared, self.pieces_placed, action);
+        }
+        if let Some(goal) = self.mode.line_goal() {
+            return (self.lines_cleared >= goal).then_some(true);
+        }
+        let dug_out = self.mode.has_garbage() && !full_rows.is_empty() && self.board.garbage_rows_without(full_rows) == 0;
+        dug_out.then_some(true)
+    }
+
+    // A split for every multiple of the mode's split interval passed since
+    // `lines_before`, up to the goal
+    fn record_splits(&mut self, lines_before: u32) {
+        let Some(every) = self.mode.split_lines() else {
+            return;
         };
-        
-        self.score += points * self.level;
+        let last = self.mode.line_goal().map_or(self.lines_cleared, |goal| goal.min(self.lines_cleared));
+        let mut lines = (lines_before / every + 1) * every;
+        while lines <= last {
+            self.splits.push(self.elapsed);
+            self.events.push(GameEvent::Split { lines, time: self.elapsed });
+            lines += every;
+        }
+    }
+
+    fn is_above_visible_field(&self, tetromino: &Tetromino) -> bool {
+        tetromino.blocks.iter().enumerate().all(|(y, row)| {
+            !row.contains(&true) || self.board.is_hidden_row(tetromino.y + y as i32)
+        })
+    }
+
+    // 3-corner rule: a T (any piece flagged `t_spin`) that last moved by
+    // rotating, with three of the four corners around its center occupied.
+    // Both "front" corners (the side the T points to) make a full T-spin,
+    // otherwise it is a mini, unless a quarter turn used the last kick (the
+    // TST/fin kick), which always counts as full.
+    fn detect_t_spin(&self, tetromino: &Tetromino) -> TSpin {
+        if !self.rules.t_spins_enabled() || !tetromino.piece.t_spin || !self.last_move_rotation {
+            return TSpin::None;
+        }
+
+        let (x, y) = (tetromino.x, tetromino.y);
+        let top_left = self.board.is_occupied(x, y);
+        let top_right = self.board.is_occupied(x + 2, y);
+        let bottom_left = self.board.is_occupied(x, y + 2);
+        let bottom_right = self.board.is_occupied(x + 2, y + 2);
+
+        let corners = [top_left, top_right, bottom_left, bottom_right]
+            .iter()
+            .filter(|&&occupied| occupied)
+            .count();
+        if corners < 3 {
+            return TSpin::None;
+        }
+
+        let (front_a, front_b) = match tetromino.rotation {
+            0 => (top_left, top_right),
+            1 => (top_right, bottom_right),
+            2 => (bottom_left, bottom_right),
+            _ => (top_left, bottom_left),
+        };
+        if (front_a && front_b) || self.last_kick_tst {
+            TSpin::Full
+        } else {
+            TSpin::Mini
+        }
     }
 
     fn update_level(&mut self) {
-        let new_level = (self.lines_cleared / 10) + 1;
+        // Zen keeps the starting speed
+        if self.mode == GameMode::Zen {
+            return;
+        }
+        let new_level = self.rules.level(self.lines_cleared);
         if new_level != self.level {
             self.level = new_level;
-            self.drop_interval = (1.0 - (self.level - 1) as f32 * 0.1).max(0.1);
+            self.apply_level_speed();
+            self.events.push(GameEvent::LevelUp(self.level));
         }
     }
 
+    fn apply_level_speed(&mut self) {
+        self.gravity = self.rules.gravity(self.level);
+        self.lock_delay = self.rules.lock_delay(self.level);
+    }
+
     pub fn toggle_pause(&mut self) {
         match self.state {
             GameState::Playing => self.state = GameState::Paused,
             GameState::Paused => self.state = GameState::Playing,
             GameState::GameOver => {}
+            GameState::Finished => {}
             GameState::Menu => {}
         }
     }
 
     pub fn reset(&mut self) {
-        self.board.clear();
+        self.rules = match self.mode {
+            GameMode::Master => Rc::new(Master::new(self.base_rules.clone())),
+            _ => self.base_rules.clone(),
+        };
+        self.board = Board::new(self.board_size);
+        self.puzzle_queue.clear();
+        if let Some(puzzle) = self.active_puzzle() {
+            let mut board = Board::new(BoardSize::STANDARD);
+            puzzle.apply_to(&mut board);
+            self.puzzle_queue = puzzle.queue.iter().cloned().collect();
+            self.board = board;
+        }
+        // Pieces wider than the board would block out as they spawn
+        self.piece_set = self.base_piece_set.for_rotation(self.rules.rotation()).fitting(self.board.width);
+        self.rng = StdRng::seed_from_u64(self.seed);
+        self.randomizer = self.randomizer_kind.create(&self.piece_set);
+        if self.mode.has_garbage() {
+            // Its own generator, so the garbage does not change the pieces
+            let mut rng = StdRng::seed_from_u64(self.seed.wrapping_add(1));
+            self.board.add_garbage(self.garbage_rows, self.messiness, &mut rng);
+        }
         self.current_tetromino = None;
-        self.next_tetromino = Tetromino::random();
+        self.next_queue.clear();
+        self.fill_queue();
+        self.hold_tetromino = None;
+        self.hold_used = false;
         self.score = 0;
         self.level = 1;
         self.lines_cleared = 0;
+        self.elapsed = 0.0;
+        self.splits.clear();
+        self.pieces_placed = 0;
+        self.reveal_timer = 0.0;
+        self.last_piece_rows = (0, 0);
+        if self.mode == GameMode::Zen {
+            self.score = self.carried_score;
+            self.lines_cleared = self.carried_lines;
+        }
         self.state = GameState::Playing;
-        self.drop_timer = 0.0;
-        self.drop_interval = 1.0;
+        self.phase = Phase::Entry { timer: 0.0 };
+        self.top_out = None;
+        self.drop_progress = 0.0;
+        self.lock_reset = self.rules.lock_reset();
+        self.delays = self.rules.delays();
+        self.handling = self.rules.handling();
+        self.apply_level_speed();
+        self.reset_lock_delay();
+        self.scoring = ScoreState::new();
+        self.last_move_rotation = false;
+        self.events.clear();
     }
 
     pub fn start_game(&mut self) {
-        self.state = GameState::Playing;
+        self.reset();
         self.spawn_tetromino();
     }
 
@@ -258,6 +1010,43 @@ impl Game {
         self.score
     }
 
+    // Seconds left in a timed mode
+    pub fn remaining_time(&self) -> Option<f32> {
+        if self.mode.is_timed() {
+            Some((self.time_limit - self.elapsed).max(0.0))
+        } else {
+            None
+        }
+    }
+
+    // How much of the locked cell at (x, y) is shown, from 0.0 (hidden) to
+    // 1.0. Only modes that hide the stack return less than 1.0, and never
+    // once the game is over.
+    pub fn cell_visibility(&self, x: usize, y: usize) -> f32 {
+        if !self.mode.hides_stack() || matches!(self.state, GameState::GameOver | GameState::Finished) {
+            return 1.0;
+        }
+        let shown = match self.mode {
+            GameMode::Invisible => match self.board.grid[y][x] {
+                Some(cell) => 1.0 - (self.elapsed - cell.placed_at) / INVISIBLE_FADE_TIME,
+                None => 0.0,
+            },
+            GameMode::Fog => {
+                // Fully clear within FOG_ROWS of the piece, fading over one more row
+                let (top, bottom) = self.current_tetromino.as_ref().map_or(self.last_piece_rows, piece_rows);
+                let distance = (top - y as i32).max(y as i32 - bottom).max(0);
+                (FOG_ROWS + 1 - distance) as f32
+            }
+            _ => 1.0,
+        };
+        shown.max(self.reveal_timer / STACK_REVEAL_TIME).clamp(0.0, 1.0)
+    }
+
+    // Rows that still hold garbage
+    pub fn garbage_left(&self) -> usize {
+        self.board.garbage_rows_without(&[])
+    }
+
     pub fn get_level(&self) -> u32 {
         self.level
     }
@@ -270,15 +1059,401 @@ impl Game {
         &self.state
     }
 
-    pub fn get_next_tetromino(&self) -> &Tetromino {
-        &self.next_tetromino
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn get_randomizer_kind(&self) -> RandomizerKind {
+        self.randomizer_kind
+    }
+
+    // The upcoming pieces that should be shown to the player
+    pub fn get_next_queue(&self) -> impl Iterator<Item = &Tetromino> {
+        self.next_queue.iter().take(self.preview_count)
+    }
+
+    pub fn get_hold_tetromino(&self) -> Option<&Tetromino> {
+        self.hold_tetromino.as_ref()
+    }
+}
+
+// Top and bottom board rows of a piece's box
+fn piece_rows(tetromino: &Tetromino) -> (i32, i32) {
+    (tetromino.y, tetromino.y + tetromino.blocks.len() as i32 - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Cell;
+    use crate::config::{BOARD_HEIGHT, MAX_LOCK_RESETS};
+    use crate::rules::{CustomRules, TgmLike};
+
+    const FRAME: f32 = 1.0 / FRAMES_PER_SECOND;
+
+    fn ticks(game: &mut Game, frames: u32) {
+        for _ in 0..frames {
+            game.tick(FRAME);
+        }
+    }
+
+    // Fills the given cells of the bottom rows, written top to bottom with
+    // '#' for a block
+    fn fill(game: &mut Game, rows: &[&str]) {
+        let top = game.board.grid.len() - rows.len();
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                game.board.grid[top + y][x] = (c == '#').then(Cell::garbage);
+            }
+        }
+    }
+
+    fn piece(game: &Game, name: &str) -> Tetromino {
+        let piece = game.piece_set.pieces.iter().find(|piece| piece.name == name).unwrap();
+        Tetromino::new(piece.clone())
     }
 
-    pub fn get_animations(&self) -> &AnimationManager {
-        &self.animations
+    // Replaces the falling piece with a fresh `name` piece at spawn
+    fn spawn(game: &mut Game, name: &str) {
+        let piece = piece(game, name);
+        game.current_tetromino = None;
+        game.next_queue.push_front(piece);
+        game.spawn_tetromino();
+    }
+
+    // Modern speed with ARS rotation, so pieces stay where they spawn
+    fn ars_game() -> Game {
+        let rules = CustomRules::from_json(r#"{ "name": "ARS", "base": "modern", "rotation": "ars" }"#).unwrap();
+        let mut game = Game::with_seed(1);
+        game.set_rules(Rc::new(rules));
+        game.start_game();
+        game
+    }
+
+    fn scored(game: &mut Game) -> Vec<ClearAction> {
+        game.drain_events()
+            .into_iter()
+            .filter_map(|event| match event {
+                GameEvent::Scored(action) => Some(action),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn commands_play_the_game_and_report_events() {
+        let mut game = Game::new();
+        game.start_game();
+        game.apply(Command::HardDrop);
+        assert!(game.board.grid.iter().flatten().any(|cell| cell.is_some()));
+        assert_eq!(game.drain_events(), [GameEvent::PiecePlaced]);
+        assert!(game.drain_events().is_empty());
+    }
+
+    #[test]
+    fn same_seed_deals_the_same_pieces() {
+        let pieces = |seed| {
+            let mut game = Game::with_seed(seed);
+            game.start_game();
+            (0..20)
+                .map(|_| {
+                    game.spawn_tetromino();
+                    game.current_tetromino.as_ref().unwrap().piece.name.clone()
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(pieces(42), pieces(42));
+        assert_ne!(pieces(42), pieces(43));
+    }
+
+    #[test]
+    fn hold_swaps_once_per_drop() {
+        let mut game = Game::with_seed(7);
+        game.start_game();
+        game.delays = Delays::constant(0.0, 0.0);
+        let first = game.current_tetromino.as_ref().unwrap().piece.name.clone();
+        let second = game.next_queue[0].piece.name.clone();
+
+        game.hold();
+        assert_eq!(game.hold_tetromino.as_ref().unwrap().piece.name, first);
+        assert_eq!(game.current_tetromino.as_ref().unwrap().piece.name, second);
+        // Locked out until the piece is placed
+        game.hold();
+        assert_eq!(game.current_tetromino.as_ref().unwrap().piece.name, second);
+
+        game.hard_drop();
+        game.hold();
+        assert_eq!(game.current_tetromino.as_ref().unwrap().piece.name, first);
+    }
+
+    #[test]
+    fn preview_size_does_not_change_the_sequence() {
+        let pieces = |preview| {
+            let mut game = Game::with_seed(42);
+            game.set_preview_count(preview);
+            game.start_game();
+            assert_eq!(game.get_next_queue().count(), preview.min(MAX_PREVIEW_COUNT));
+            (0..20)
+                .map(|_| {
+                    game.spawn_tetromino();
+                    game.current_tetromino.as_ref().unwrap().piece.name.clone()
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(pieces(0), pieces(3));
+        assert_eq!(pieces(0), pieces(MAX_PREVIEW_COUNT + 1));
+    }
+
+    #[test]
+    fn moves_restart_the_lock_delay_up_to_the_reset_limit() {
+        let mut game = Game::with_seed(1);
+        game.start_game();
+        game.delays = Delays::constant(0.0, 0.0);
+        while !game.is_grounded() {
+            game.move_down();
+        }
+        game.drain_events();
+
+        game.tick(0.3);
+        game.move_left();
+        game.tick(0.3);
+        assert!(game.drain_events().is_empty());
+        game.tick(0.3);
+        assert_eq!(game.drain_events(), [GameEvent::PiecePlaced]);
+
+        // Out of resets, the next grounded tick locks at once
+        while !game.is_grounded() {
+            game.move_down();
+        }
+        game.lock_resets = MAX_LOCK_RESETS;
+        game.tick(0.01);
+        assert_eq!(game.drain_events(), [GameEvent::PiecePlaced]);
     }
 
-    pub fn get_animations_mut(&mut self) -> &mut AnimationManager {
-        &mut self.animations
+    #[test]
+    fn scores_a_t_spin_double() {
+        let mut game = Game::with_seed(1);
+        game.start_game();
+        fill(&mut game, &["...#......", "###...####", "####.#####"]);
+        // A T pointing down into the slot, under the overhang, as if it had
+        // just rotated in
+        let mut tetromino = piece(&game, "T");
+        tetromino.set_rotation(2);
+        tetromino.x = 3;
+        tetromino.y = game.board.grid.len() as i32 - 3;
+        game.current_tetromino = Some(tetromino);
+        game.last_move_rotation = true;
+        game.drain_events();
+
+        game.hard_drop();
+        let actions = scored(&mut game);
+        assert_eq!(actions.len(), 1);
+        assert_eq!((actions[0].t_spin, actions[0].lines), (TSpin::Full, 2));
+        assert_eq!(game.score, 1200);
+        assert_eq!(game.lines_cleared, 2);
+    }
+
+    #[test]
+    fn half_turn_kicks_do_not_upgrade_mini_t_spins() {
+        let mut game = Game::with_seed(1);
+        game.start_game();
+        // A T pointing right at (3, 10) whose 180 degree turn only fits with
+        // the fifth kick, two rows up, leaving three corners filled but only
+        // one in front
+        let mut tetromino = piece(&game, "T");
+        tetromino.set_rotation(1);
+        tetromino.x = 3;
+        tetromino.y = 10;
+        game.current_tetromino = Some(tetromino);
+        for (x, y) in [(3, 8), (5, 8), (5, 10), (3, 11)] {
+            game.board.grid[y][x] = Some(Cell::garbage());
+        }
+
+        game.rotate_180();
+        let tetromino = game.current_tetromino.clone().unwrap();
+        assert_eq!((tetromino.rotation, tetromino.x, tetromino.y), (3, 3, 8));
+        assert_eq!(game.detect_t_spin(&tetromino), TSpin::Mini);
+    }
+
+    #[test]
+    fn narrow_boards_leave_out_pieces_wider_than_them() {
+        let narrow = BoardSize::PRESETS[1];
+        for set in PieceSet::builtin() {
+            let mut game = Game::with_seed(1);
+            game.set_piece_set(set.clone());
+            game.set_board_size(narrow);
+            game.start_game();
+            assert!(game.piece_set.pieces.len() <= set.pieces.len());
+            for name in game.piece_set.pieces.iter().map(|piece| piece.name.clone()).collect::<Vec<_>>() {
+                spawn(&mut game, &name);
+                assert_eq!(game.state, GameState::Playing, "{} {}", set.name, name);
+            }
+        }
+    }
+
+    #[test]
+    fn ars_plays_the_classic_set_with_ars_shapes() {
+        let mut game = Game::with_seed(1);
+        game.start_game();
+        assert_eq!(game.piece_set.name, "CLASSIC");
+        assert_eq!(ars_game().piece_set.name, "CLASSIC (ARS)");
+
+        game.set_piece_set(PieceSet::pentomino());
+        game.set_rules(Rc::new(TgmLike));
+        game.reset();
+        assert_eq!(game.piece_set.name, "PENTOMINO");
+    }
+
+    #[test]
+    fn ars_does_not_kick_when_the_center_column_is_blocked_first() {
+        let mut game = ars_game();
+        spawn(&mut game, "T");
+        let (x, y) = {
+            let tetromino = game.current_tetromino.as_ref().unwrap();
+            (tetromino.x, tetromino.y)
+        };
+        // Right above the T's middle: the plain rotation fails there, and a
+        // kick to the right would fit
+        game.board.grid[y as usize][x as usize + 1] = Some(Cell::garbage());
+        game.rotate();
+        let tetromino = game.current_tetromino.as_ref().unwrap();
+        assert_eq!((tetromino.rotation, tetromino.x), (0, x));
+    }
+
+    #[test]
+    fn ars_kicks_off_the_wall() {
+        let mut game = ars_game();
+        spawn(&mut game, "T");
+        game.rotate();
+        while game.try_shift(1) {}
+        let x = game.current_tetromino.as_ref().unwrap().x;
+
+        // Pointing left against the right wall, the flat state only fits one
+        // column to the left
+        game.rotate();
+        let tetromino = game.current_tetromino.as_ref().unwrap();
+        assert_eq!((tetromino.rotation, tetromino.x), (2, x - 1));
+    }
+
+    #[test]
+    fn step_reset_restarts_lock_delay_on_a_new_row_at_20g() {
+        let mut game = Game::with_seed(1);
+        game.set_rules(Rc::new(TgmLike));
+        game.start_game();
+        fill(&mut game, &["#####.....", "#####.....", "#####.....", "#####....."]);
+        spawn(&mut game, "O");
+        let ledge_y = game.current_tetromino.as_ref().unwrap().y;
+        game.drain_events();
+
+        // Most of the lock delay runs out on the ledge, then the piece slides off
+        ticks(&mut game, 24);
+        assert!(game.drain_events().is_empty());
+        game.move_right();
+        ticks(&mut game, 1);
+        assert!(game.current_tetromino.as_ref().unwrap().y > ledge_y);
+
+        // It gets a full lock delay on the floor
+        assert!(game.lock_timer < 2.0 * FRAME);
+        ticks(&mut game, 24);
+        assert!(game.drain_events().is_empty());
+        ticks(&mut game, 12);
+        assert_eq!(game.drain_events(), [GameEvent::PiecePlaced]);
+    }
+
+    #[test]
+    fn sprint_records_splits_and_finishes_at_the_goal() {
+        let mut game = Game::with_seed(1);
+        game.set_mode(GameMode::Sprint);
+        game.start_game();
+        game.elapsed = 12.5;
+        game.lines_cleared = 12;
+        game.record_splits(8);
+        assert_eq!(game.splits, [12.5]);
+
+        // Splits stop at the goal
+        game.elapsed = 60.0;
+        game.lines_cleared = 43;
+        game.record_splits(38);
+        assert_eq!(game.splits, [12.5, 60.0]);
+        let none = ScoreState::new().score_lock(0, TSpin::None, false, 1);
+        assert_eq!(game.objective_result(&[], &none), Some(true));
+    }
+
+    #[test]
+    fn cascade_chains_multiply_their_points() {
+        let mut game = Game::with_seed(1);
+        game.set_line_gravity(LineGravity::Cascade);
+        game.start_game();
+        fill(&mut game, &[".........#", "....######", "#########.", "#########."]);
+        let mut tetromino = piece(&game, "I");
+        tetromino.x = 0;
+        tetromino.y = game.board.rows() as i32 - 5;
+        game.current_tetromino = Some(tetromino);
+        game.drain_events();
+
+        // The I fills a row; once it is gone the loose block drops into the
+        // bottom row's hole and clears it too
+        game.hard_drop();
+        ticks(&mut game, 60);
+        let actions = scored(&mut game);
+        assert_eq!(actions.len(), 2);
+        assert_eq!((actions[1].chain, actions[1].lines), (1, 1));
+        assert_eq!((actions[1].combo, actions[1].points), (0, 2 * 100));
+        assert_eq!(game.lines_cleared, 2);
+    }
+
+    #[test]
+    fn dig_keeps_garbage_below_the_board_height() {
+        let mut game = Game::with_seed(1);
+        game.set_mode(GameMode::Dig);
+        game.set_garbage(100, 0.0);
+        game.start_game();
+        assert_eq!(game.garbage_rows, BOARD_HEIGHT - 1);
+        assert_eq!(game.garbage_left(), game.garbage_rows);
+    }
+
+    #[test]
+    fn fog_stays_clear_around_the_last_piece_during_entry_delay() {
+        let mut game = Game::with_seed(1);
+        game.set_mode(GameMode::Fog);
+        game.set_rules(Rc::new(TgmLike));
+        game.start_game();
+        fill(&mut game, &["#########."]);
+        spawn(&mut game, "O");
+        ticks(&mut game, 31);
+        assert!(matches!(game.phase, Phase::Entry { .. }));
+        assert_eq!(game.cell_visibility(0, game.board.rows() - 1), 1.0);
+        assert_eq!(game.cell_visibility(0, 0), 0.0);
+    }
+
+    #[test]
+    fn instant_gravity_drops_into_holes_between_auto_shift_steps() {
+        let mut game = Game::with_seed(1);
+        game.set_rules(Rc::new(TgmLike));
+        game.start_game();
+        game.handling.arr = 0.0;
+        fill(&mut game, &["##..######", "##..######"]);
+        spawn(&mut game, "O");
+
+        // An instant auto shift would carry the O over the hole to the wall
+        game.press(HeldInput::Left);
+        ticks(&mut game, 60);
+        assert_eq!(game.get_lines_cleared(), 2);
+    }
+
+    #[test]
+    fn zen_clears_the_top_instead_of_ending() {
+        let mut game = Game::with_seed(1);
+        game.set_mode(GameMode::Zen);
+        game.set_carry_over(1500, 42);
+        game.start_game();
+        assert_eq!((game.score, game.lines_cleared), (1500, 42));
+
+        let rows = vec!["####.#####"; game.board.rows()];
+        fill(&mut game, &rows);
+        spawn(&mut game, "O");
+        assert_eq!(game.state, GameState::Playing);
+        assert_eq!(game.score, 1500 - ZEN_TOP_OUT_PENALTY);
+        assert!(game.drain_events().contains(&GameEvent::StackCleared { penalty: ZEN_TOP_OUT_PENALTY }));
     }
 }
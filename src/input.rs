@@ -0,0 +1,187 @@
+use crate::config::{DAS, ARR, SOFT_DROP_FACTOR};
+
+// Inputs that act for as long as they are held, as opposed to one-shot `Command`s
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HeldInput {
+    Left,
+    Right,
+    SoftDrop,
+}
+
+// Movement handling, in seconds. With `arr` at 0 the piece goes straight to the wall.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Handling {
+    pub das: f32,
+    pub arr: f32,
+    pub soft_drop_factor: f32,
+}
+
+impl Default for Handling {
+    fn default() -> Self {
+        Handling {
+            das: DAS,
+            arr: ARR,
+            soft_drop_factor: SOFT_DROP_FACTOR,
+        }
+    }
+}
+
+// Tracks which inputs are held and the delayed auto shift timers, so movement
+// speed does not depend on the operating system's key repeat settings
+#[derive(Debug, Clone, Default)]
+pub struct InputState {
+    left_held: bool,
+    right_held: bool,
+    soft_drop_held: bool,
+    // Most recently pressed direction wins when both are held
+    direction: i32,
+    das_timer: f32,
+    arr_timer: f32,
+}
+
+impl InputState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn press(&mut self, input: HeldInput) {
+        match input {
+            HeldInput::Left => {
+                self.left_held = true;
+                self.start_direction(-1);
+            }
+            HeldInput::Right => {
+                self.right_held = true;
+                self.start_direction(1);
+            }
+            HeldInput::SoftDrop => self.soft_drop_held = true,
+        }
+    }
+
+    pub fn release(&mut self, input: HeldInput) {
+        match input {
+            HeldInput::Left => {
+                self.left_held = false;
+                if self.direction == -1 {
+                    self.fall_back();
+                }
+            }
+            HeldInput::Right => {
+                self.right_held = false;
+                if self.direction == 1 {
+                    self.fall_back();
+                }
+            }
+            HeldInput::SoftDrop => self.soft_drop_held = false,
+        }
+    }
+
+    pub fn is_held(&self, input: HeldInput) -> bool {
+        match input {
+            HeldInput::Left => self.left_held,
+            HeldInput::Right => self.right_held,
+            HeldInput::SoftDrop => self.soft_drop_held,
+        }
+    }
+
+    // -1 for left, 1 for right, 0 when no direction is held
+    pub fn direction(&self) -> i32 {
+        self.direction
+    }
+
+    fn reset_timers(&mut self) {
+        self.das_timer = 0.0;
+        self.arr_timer = 0.0;
+    }
+
+    // Advance the timers and return how many auto-repeat steps are due.
+    // `max_steps` caps instant (ARR 0) movement, usually the board width.
+    pub fn auto_shift_steps(&mut self, dt: f32, handling: &Handling, max_steps: u32) -> u32 {
+        if self.direction == 0 {
+            return 0;
+        }
+
+        let was_charged = self.das_timer >= handling.das;
+        self.das_timer += dt;
+        if self.das_timer < handling.das {
+            return 0;
+        }
+
+        if handling.arr <= 0.0 {
+            return max_steps;
+        }
+
+        // Time past the DAS threshold counts towards the first repeat
+        if was_charged {
+            self.arr_timer += dt;
+        } else {
+            self.arr_timer += self.das_timer - handling.das;
+        }
+
+        let mut steps = if was_charged { 0 } else { 1 };
+        while self.arr_timer >= handling.arr && steps < max_steps {
+            self.arr_timer -= handling.arr;
+            steps += 1;
+        }
+        steps
+    }
+
+    fn start_direction(&mut self, direction: i32) {
+        self.direction = direction;
+        self.reset_timers();
+    }
+
+    fn fall_back(&mut self) {
+        if self.left_held {
+            self.start_direction(-1);
+        } else if self.right_held {
+            self.start_direction(1);
+        } else {
+            self.direction = 0;
+            self.reset_timers();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HANDLING: Handling = Handling { das: 0.1, arr: 0.05, soft_drop_factor: 20.0 };
+
+    #[test]
+    fn repeats_after_das_then_every_arr() {
+        let mut input = InputState::new();
+        input.press(HeldInput::Right);
+        assert_eq!(input.auto_shift_steps(0.09, &HANDLING, 10), 0);
+        // DAS charged, and the time past it counts towards the next repeat
+        assert_eq!(input.auto_shift_steps(0.02, &HANDLING, 10), 1);
+        assert_eq!(input.auto_shift_steps(0.02, &HANDLING, 10), 0);
+        assert_eq!(input.auto_shift_steps(0.11, &HANDLING, 10), 2);
+        assert_eq!(input.auto_shift_steps(1.0, &HANDLING, 10), 10);
+    }
+
+    #[test]
+    fn zero_arr_goes_to_the_wall() {
+        let handling = Handling { arr: 0.0, ..HANDLING };
+        let mut input = InputState::new();
+        input.press(HeldInput::Left);
+        assert_eq!(input.auto_shift_steps(0.1, &handling, 7), 7);
+    }
+
+    #[test]
+    fn last_pressed_direction_wins() {
+        let mut input = InputState::new();
+        input.press(HeldInput::Left);
+        input.auto_shift_steps(0.2, &HANDLING, 10);
+        input.press(HeldInput::Right);
+        assert_eq!(input.direction(), 1);
+        // Switching restarts DAS
+        assert_eq!(input.auto_shift_steps(0.05, &HANDLING, 10), 0);
+        input.release(HeldInput::Right);
+        assert_eq!(input.direction(), -1);
+        input.release(HeldInput::Left);
+        assert_eq!(input.direction(), 0);
+        assert_eq!(input.auto_shift_steps(1.0, &HANDLING, 10), 0);
+    }
+}
@@ -0,0 +1,19 @@
+// Game core: rules and simulation only, no window or ggez dependency.
+// The graphical frontend (main.rs) sends commands and draws the state.
+
+pub mod board;
+pub mod config;
+pub mod cycle;
+pub mod game;
+pub mod input;
+pub mod mode;
+pub mod piece_set;
+pub mod polyomino;
+pub mod puzzle;
+pub mod randomizer;
+pub mod records;
+pub mod rotation;
+pub mod rules;
+pub mod scoring;
+pub mod tetromino;
+pub mod timing;
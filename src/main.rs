@@ -1,47 +1,211 @@
-mod game;
-mod tetromino;
-mod board;
 mod audio;
 mod ui;
-mod config;
 mod menu;
 mod animations;
+mod render;
+
+use std::path::Path;
+use std::rc::Rc;
 
 use ggez::{Context, ContextBuilder, GameResult};
 use ggez::event::{self, EventHandler};
 use ggez::graphics::{self, Color};
 use ggez::input::keyboard::{KeyCode, KeyInput};
 
-use game::Game;
+use purple_box_destruction::config::{
+    WINDOW_WIDTH, WINDOW_HEIGHT, BACKGROUND_COLOR, RECORDS_FILE, TIME_WARNING, ULTRA_TIME_LIMIT,
+    DIG_GARBAGE_ROWS, DIG_MESSINESS,
+};
+use purple_box_destruction::game::{self, Command, Game, GameEvent};
+use purple_box_destruction::input::HeldInput;
+use purple_box_destruction::mode::GameMode;
+use purple_box_destruction::piece_set::PieceSet;
+use purple_box_destruction::puzzle::PuzzlePack;
+use purple_box_destruction::records::{Records, ZenTotals};
+use purple_box_destruction::rules::{self, CustomRules, RuleSet};
+
+use animations::AnimationManager;
+use audio::AudioManager;
+use render::BoardRenderer;
 use ui::UI;
 use menu::Menu;
-use config::{WINDOW_WIDTH, WINDOW_HEIGHT, BACKGROUND_COLOR};
+
+// Mode parameters given on the command line
+struct ModeSettings {
+    time_limit: f32,
+    garbage_rows: usize,
+    messiness: f32,
+}
 
 struct TetrisGame {
     game: Game,
     ui: UI,
     menu: Menu,
+    renderer: BoardRenderer,
+    animations: AnimationManager,
+    audio: AudioManager,
+    records: Records,
+    mode_settings: ModeSettings,
 }
 
 impl TetrisGame {
-    fn new(ctx: &mut Context) -> GameResult<TetrisGame> {
-        let game = Game::new();
+    // Custom rules and piece sets are listed before the built-in ones, so the
+    // first of them starts selected
+    fn new(
+        ctx: &mut Context,
+        seed: Option<u64>,
+        mode_settings: ModeSettings,
+        custom_rules: Vec<Rc<dyn RuleSet>>,
+        custom_sets: Vec<PieceSet>,
+        custom_puzzles: Vec<PuzzlePack>,
+    ) -> GameResult<TetrisGame> {
+        let game = Game::with_seed(seed.unwrap_or_else(Game::random_seed));
         let ui = UI::new(ctx)?;
-        let menu = Menu::new();
+        let mut rule_sets = custom_rules;
+        rule_sets.extend(rules::builtin());
+        let mut piece_sets = custom_sets;
+        piece_sets.extend(PieceSet::builtin());
+        let mut packs = custom_puzzles;
+        packs.push(PuzzlePack::starter());
+        let puzzles = packs.into_iter().flat_map(|pack| pack.puzzles).collect();
+        let mut menu = Menu::new(game.get_seed(), rule_sets, piece_sets, puzzles);
+        let renderer = BoardRenderer::fit(&game.board);
+        let animations = AnimationManager::new(game.get_seed());
+        let audio = AudioManager::new();
+        let records = Records::load(Path::new(RECORDS_FILE));
+        menu.set_solved(records.solved.clone());
         
-        Ok(TetrisGame { game, ui, menu })
+        Ok(TetrisGame { game, ui, menu, renderer, animations, audio, records, mode_settings })
+    }
+
+    fn start_game(&mut self) {
+        self.game.set_mode(self.menu.get_mode());
+        self.game.set_time_limit(self.mode_settings.time_limit);
+        self.game.set_puzzle(Some(self.menu.get_puzzle().clone()));
+        self.game.set_carry_over(self.records.zen.score, self.records.zen.lines);
+        self.game.set_seed(self.menu.get_seed());
+        self.game.set_rules(self.menu.get_rules());
+        self.game.set_piece_set(self.menu.get_piece_set().clone());
+        self.game.set_randomizer(self.menu.get_randomizer());
+        self.game.set_preview_count(self.menu.get_preview_count());
+        self.game.set_board_size(self.menu.get_board_size());
+        self.game.set_garbage(self.mode_settings.garbage_rows, self.mode_settings.messiness);
+        self.game.set_line_gravity(self.menu.get_line_gravity());
+        self.game.start_game();
+        self.renderer = BoardRenderer::fit(&self.game.board);
+        self.animations.reset(self.game.get_seed());
+        self.show_personal_best();
+    }
+
+    fn show_personal_best(&mut self) {
+        let best = self.personal_best();
+        self.ui.set_personal_best(best, false);
+    }
+
+    // Best time or score for the current mode and piece set, formatted
+    fn personal_best(&self) -> Option<String> {
+        let set = &self.game.piece_set.name;
+        match self.game.mode {
+            GameMode::Sprint => self.records.sprint_best(set).map(ui::format_time),
+            GameMode::Ultra => self.records.ultra_best(set, self.game.time_limit).map(|score| score.to_string()),
+            GameMode::Dig => self.records.dig_best(set, self.game.garbage_rows).map(ui::format_time),
+            _ => None,
+        }
+    }
+
+    // Keeps the finished game's time or score if it is a new best
+    fn submit_record(&mut self) {
+        let set = self.game.piece_set.name.clone();
+        let new_record = match self.game.mode {
+            GameMode::Sprint => self.records.submit_sprint(&set, self.game.elapsed),
+            GameMode::Ultra => self.records.submit_ultra(&set, self.game.time_limit, self.game.get_score()),
+            GameMode::Dig => self.records.submit_dig(&set, self.game.garbage_rows, self.game.elapsed),
+            GameMode::Puzzle => match self.game.puzzle {
+                Some(ref puzzle) => self.records.mark_solved(&puzzle.id()),
+                None => false,
+            },
+            _ => false,
+        };
+        self.menu.set_solved(self.records.solved.clone());
+        if new_record {
+            self.save_records();
+        }
+        let best = self.personal_best();
+        self.ui.set_personal_best(best, new_record);
+    }
+
+    // Keeps the zen score and lines for the next session
+    fn save_zen(&mut self) {
+        if self.game.mode != GameMode::Zen {
+            return;
+        }
+        let totals = ZenTotals {
+            score: self.game.get_score(),
+            lines: self.game.get_lines_cleared(),
+        };
+        self.game.set_carry_over(totals.score, totals.lines);
+        if self.records.zen != totals {
+            self.records.zen = totals;
+            self.save_records();
+        }
+    }
+
+    fn save_records(&self) {
+        if let Err(error) = self.records.save(Path::new(RECORDS_FILE)) {
+            eprintln!("{}: {}", RECORDS_FILE, error);
+        }
+    }
+
+    // Turn core events into effects on the frontend side
+    fn handle_events(&mut self) {
+        for event in self.game.drain_events() {
+            match event {
+                GameEvent::PiecePlaced => self.audio.play_tetromino_place(),
+                GameEvent::LinesCleared(lines) => {
+                    self.audio.play_line_clear();
+                    self.animations.add_line_clear_animation(
+                        &lines,
+                        self.game.board.width,
+                        self.renderer.block_size,
+                        self.renderer.offset_x,
+                        self.renderer.origin_y(),
+                    );
+                }
+                GameEvent::LevelUp(_) => self.audio.play_level_up(),
+                GameEvent::Scored(action) => self.ui.show_action(action.name()),
+                GameEvent::Split { lines, time } => {
+                    self.ui.show_action(format!("{} LINES {}", lines, ui::format_time(time)));
+                }
+                GameEvent::StackCleared { penalty } => {
+                    self.audio.play_game_over();
+                    self.ui.show_action(format!("STACK CLEARED -{}", penalty));
+                    self.save_zen();
+                }
+                GameEvent::TimeWarning => {
+                    self.ui.show_action(format!("{} SECONDS LEFT", TIME_WARNING as u32));
+                }
+                GameEvent::GameOver => self.audio.play_game_over(),
+                GameEvent::Finished => {
+                    self.audio.play_level_up();
+                    self.submit_record();
+                }
+            }
+        }
     }
 }
 
 impl EventHandler for TetrisGame {
     fn update(&mut self, ctx: &mut Context) -> GameResult {
+        let dt = ctx.time.delta().as_secs_f32();
         match self.game.get_state() {
             game::GameState::Menu => {
-                self.menu.update(ctx);
+                self.menu.update(dt);
             }
             _ => {
-                self.game.update(ctx);
-                self.game.get_animations_mut().update(ctx.time.delta().as_secs_f32());
+                self.game.tick(dt);
+                self.handle_events();
+                self.animations.update(dt);
+                self.ui.update(dt);
             }
         }
         Ok(())
@@ -61,9 +225,9 @@ impl EventHandler for TetrisGame {
                 self.menu.draw(ctx, &mut canvas)?;
             }
             _ => {
-                self.game.draw(ctx, &mut canvas)?;
-                self.game.get_animations().draw(ctx, &mut canvas)?;
-                self.ui.draw(ctx, &mut canvas, &self.game)?;
+                self.renderer.draw(ctx, &mut canvas, &self.game)?;
+                self.animations.draw(ctx, &mut canvas)?;
+                self.ui.draw(ctx, &mut canvas, &self.game, &self.renderer)?;
             }
         }
         
@@ -75,48 +239,196 @@ impl EventHandler for TetrisGame {
         &mut self,
         _ctx: &mut Context,
         input: KeyInput,
-        _repeat: bool,
+        repeat: bool,
     ) -> GameResult {
         match self.game.get_state() {
             game::GameState::Menu => {
                 match input.keycode {
-                    Some(KeyCode::Up) | Some(KeyCode::Down) => {
+                    Some(KeyCode::Up) => {
+                        self.menu.select_previous();
+                    }
+                    Some(KeyCode::Down) => {
                         self.menu.select_next();
                     }
+                    Some(KeyCode::Left) => {
+                        self.menu.change_option(false);
+                    }
+                    Some(KeyCode::Right) => {
+                        self.menu.change_option(true);
+                    }
                     Some(KeyCode::Return) => {
                         match self.menu.get_selected_option() {
                             menu::MenuOption::Start => {
-                                self.game.start_game();
+                                self.start_game();
+                            }
+                            menu::MenuOption::Seed => {
+                                self.menu.set_seed(Game::random_seed());
+                            }
+                            menu::MenuOption::Mode
+                            | menu::MenuOption::Puzzle
+                            | menu::MenuOption::Rules
+                            | menu::MenuOption::Pieces
+                            | menu::MenuOption::Randomizer
+                            | menu::MenuOption::Preview
+                            | menu::MenuOption::BoardSize
+                            | menu::MenuOption::LineGravity => {
+                                self.menu.change_option(true);
                             }
                             menu::MenuOption::Quit => {
                                 std::process::exit(0);
                             }
                         }
                     }
+                    Some(KeyCode::Back) if *self.menu.get_selected_option() == menu::MenuOption::Seed => {
+                        self.menu.pop_seed_digit();
+                    }
                     Some(KeyCode::Escape) => {
                         std::process::exit(0);
                     }
+                    Some(keycode) if *self.menu.get_selected_option() == menu::MenuOption::Seed => {
+                        if let Some(digit) = seed_digit(keycode) {
+                            self.menu.push_seed_digit(digit);
+                        }
+                    }
                     _ => {}
                 }
             }
             _ => {
-                match input.keycode {
-                    Some(KeyCode::Left) => self.game.move_left(),
-                    Some(KeyCode::Right) => self.game.move_right(),
-                    Some(KeyCode::Down) => self.game.move_down(),
-                    Some(KeyCode::Up) => self.game.rotate(),
-                    Some(KeyCode::Space) => self.game.hard_drop(),
-                    Some(KeyCode::P) => self.game.toggle_pause(),
-                    Some(KeyCode::R) => self.game.reset(),
+                // Auto repeat is handled by the game, not by the OS
+                if repeat {
+                    return Ok(());
+                }
+                if let Some(held) = input.keycode.and_then(held_input) {
+                    self.game.press(held);
+                    self.handle_events();
+                    return Ok(());
+                }
+                let command = match input.keycode {
+                    Some(KeyCode::Up) | Some(KeyCode::X) => Some(Command::Rotate),
+                    Some(KeyCode::Z) => Some(Command::RotateLeft),
+                    Some(KeyCode::A) => Some(Command::Rotate180),
+                    Some(KeyCode::Space) => Some(Command::HardDrop),
+                    Some(KeyCode::C) | Some(KeyCode::LShift) | Some(KeyCode::RShift) => Some(Command::Hold),
+                    Some(KeyCode::P) => Some(Command::TogglePause),
+                    Some(KeyCode::R) => {
+                        self.save_zen();
+                        self.animations.reset(self.game.get_seed());
+                        self.show_personal_best();
+                        Some(Command::Reset)
+                    }
                     Some(KeyCode::Escape) => {
+                        self.save_zen();
                         self.game.return_to_menu();
+                        None
                     }
-                    _ => {}
+                    _ => None,
+                };
+                if let Some(command) = command {
+                    self.game.apply(command);
+                    self.handle_events();
                 }
             }
         }
         Ok(())
     }
+
+    fn quit_event(&mut self, _ctx: &mut Context) -> GameResult<bool> {
+        self.save_zen();
+        Ok(false)
+    }
+
+    fn key_up_event(&mut self, _ctx: &mut Context, input: KeyInput) -> GameResult {
+        if let Some(held) = input.keycode.and_then(held_input) {
+            self.game.release(held);
+        }
+        Ok(())
+    }
+}
+
+fn held_input(keycode: KeyCode) -> Option<HeldInput> {
+    match keycode {
+        KeyCode::Left => Some(HeldInput::Left),
+        KeyCode::Right => Some(HeldInput::Right),
+        KeyCode::Down => Some(HeldInput::SoftDrop),
+        _ => None,
+    }
+}
+
+fn seed_digit(keycode: KeyCode) -> Option<u64> {
+    let digit = match keycode {
+        KeyCode::Key0 | KeyCode::Numpad0 => 0,
+        KeyCode::Key1 | KeyCode::Numpad1 => 1,
+        KeyCode::Key2 | KeyCode::Numpad2 => 2,
+        KeyCode::Key3 | KeyCode::Numpad3 => 3,
+        KeyCode::Key4 | KeyCode::Numpad4 => 4,
+        KeyCode::Key5 | KeyCode::Numpad5 => 5,
+        KeyCode::Key6 | KeyCode::Numpad6 => 6,
+        KeyCode::Key7 | KeyCode::Numpad7 => 7,
+        KeyCode::Key8 | KeyCode::Numpad8 => 8,
+        KeyCode::Key9 | KeyCode::Numpad9 => 9,
+        _ => return None,
+    };
+    Some(digit)
+}
+
+// Reads `--seed <n>` from the command line
+fn parse_seed_arg() -> Option<u64> {
+    parse_arg("--seed")
+}
+
+// Reads `--ultra-time <seconds>`, `--dig-rows <n>` and `--messiness <0-1>`
+// from the command line
+fn parse_mode_args() -> ModeSettings {
+    ModeSettings {
+        time_limit: parse_arg("--ultra-time")
+            .filter(|&seconds: &f32| seconds > 0.0)
+            .unwrap_or(ULTRA_TIME_LIMIT),
+        garbage_rows: parse_arg("--dig-rows").unwrap_or(DIG_GARBAGE_ROWS),
+        messiness: parse_arg("--messiness").unwrap_or(DIG_MESSINESS),
+    }
+}
+
+fn parse_arg<T: std::str::FromStr>(name: &str) -> Option<T> {
+    let args: Vec<String> = std::env::args().collect();
+    args.iter()
+        .position(|arg| arg == name)
+        .and_then(|i| args.get(i + 1))
+        .and_then(|value| value.parse().ok())
+}
+
+// Loads the file after every `flag` on the command line. Files that fail to
+// load are reported and skipped.
+fn parse_file_args<T, E: std::fmt::Display>(flag: &str, loader: impl Fn(&Path) -> Result<T, E>) -> Vec<T> {
+    let args: Vec<String> = std::env::args().collect();
+    let mut loaded = Vec::new();
+    for (i, arg) in args.iter().enumerate() {
+        if arg != flag {
+            continue;
+        }
+        let Some(path) = args.get(i + 1) else {
+            continue;
+        };
+        match loader(Path::new(path)) {
+            Ok(value) => loaded.push(value),
+            Err(error) => eprintln!("{}: {}", path, error),
+        }
+    }
+    loaded
+}
+
+// Piece sets from `--pieces <file.json>`
+fn parse_pieces_args() -> Vec<PieceSet> {
+    parse_file_args("--pieces", PieceSet::load)
+}
+
+// Puzzle packs from `--puzzles <file.json>`
+fn parse_puzzles_args() -> Vec<PuzzlePack> {
+    parse_file_args("--puzzles", PuzzlePack::load)
+}
+
+// Rule sets from `--rules <file.json>`
+fn parse_rules_args() -> Vec<Rc<dyn RuleSet>> {
+    parse_file_args("--rules", |path| CustomRules::load(path).map(|rules| Rc::new(rules) as Rc<dyn RuleSet>))
 }
 
 fn main() -> GameResult {
@@ -125,6 +437,6 @@ fn main() -> GameResult {
         .window_mode(ggez::conf::WindowMode::default().dimensions(WINDOW_WIDTH, WINDOW_HEIGHT))
         .build()?;
 
-    let game = TetrisGame::new(&mut ctx)?;
+    let game = TetrisGame::new(&mut ctx, parse_seed_arg(), parse_mode_args(), parse_rules_args(), parse_pieces_args(), parse_puzzles_args())?;
     event::run(ctx, event_loop, game)
 }
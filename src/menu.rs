@@ -1,29 +1,77 @@
+use std::collections::BTreeSet;
+use std::rc::Rc;
+
 use ggez::{Context, GameResult};
 use ggez::graphics::{self, Color, DrawMode, DrawParam, Mesh, Rect, Text, TextFragment};
 use ggez::mint::Point2;
-use crate::config::*;
+use purple_box_destruction::config::*;
+use purple_box_destruction::cycle::cycle_index;
+use purple_box_destruction::board::{BoardSize, LineGravity};
+use purple_box_destruction::mode::GameMode;
+use purple_box_destruction::piece_set::PieceSet;
+use purple_box_destruction::puzzle::Puzzle;
+use purple_box_destruction::randomizer::RandomizerKind;
+use purple_box_destruction::rules::RuleSet;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum MenuOption {
     Start,
+    Mode,
+    Puzzle,
+    Seed,
+    Rules,
+    Pieces,
+    Randomizer,
+    Preview,
+    BoardSize,
+    LineGravity,
     Quit,
 }
 
 pub struct Menu {
     selected_option: MenuOption,
     animation_timer: f32,
+    mode: GameMode,
+    puzzles: Vec<Puzzle>,
+    puzzle: usize,
+    // Ids of the puzzles already solved
+    solved: BTreeSet<String>,
+    seed: u64,
+    rule_sets: Vec<Rc<dyn RuleSet>>,
+    rules: usize,
+    piece_sets: Vec<PieceSet>,
+    piece_set: usize,
+    randomizer: RandomizerKind,
+    preview_count: usize,
+    board_size: BoardSize,
+    line_gravity: LineGravity,
 }
 
 impl Menu {
-    pub fn new() -> Self {
+    // `rule_sets`, `piece_sets` and `puzzles` must not be empty; the first of
+    // each starts selected
+    pub fn new(seed: u64, rule_sets: Vec<Rc<dyn RuleSet>>, piece_sets: Vec<PieceSet>, puzzles: Vec<Puzzle>) -> Self {
+        let randomizer = piece_sets[0].randomizer;
         Menu {
             selected_option: MenuOption::Start,
             animation_timer: 0.0,
+            mode: GameMode::Marathon,
+            puzzles,
+            puzzle: 0,
+            solved: BTreeSet::new(),
+            seed,
+            rule_sets,
+            rules: 0,
+            piece_sets,
+            piece_set: 0,
+            randomizer,
+            preview_count: DEFAULT_PREVIEW_COUNT,
+            board_size: BoardSize::STANDARD,
+            line_gravity: LineGravity::Naive,
         }
     }
 
-    pub fn update(&mut self, ctx: &mut Context) {
-        let dt = ctx.time.delta().as_secs_f32();
+    pub fn update(&mut self, dt: f32) {
         self.animation_timer += dt;
     }
 
@@ -69,77 +117,70 @@ impl Menu {
             title_color.a,
         );
 
-        self.draw_text(canvas, "PURPLE BOX DESTRUCTION", WINDOW_WIDTH / 2.0 - 200.0, 100.0, 36.0, animated_color)?;
+        self.draw_text(canvas, "PURPLE BOX DESTRUCTION", WINDOW_WIDTH / 2.0 - 200.0, 50.0, 36.0, animated_color)?;
 
         Ok(())
     }
 
     fn draw_menu_options(&self, _ctx: &mut Context, canvas: &mut graphics::Canvas) -> GameResult {
         let center_x = WINDOW_WIDTH / 2.0;
-        let start_y = 250.0;
-
-        // Start option
-        let start_color = if self.selected_option == MenuOption::Start {
-            Color::new(
-                MENU_HIGHLIGHT_COLOR.0,
-                MENU_HIGHLIGHT_COLOR.1,
-                MENU_HIGHLIGHT_COLOR.2,
-                MENU_HIGHLIGHT_COLOR.3,
-            )
-        } else {
-            Color::new(
-                MENU_TEXT_COLOR.0,
-                MENU_TEXT_COLOR.1,
-                MENU_TEXT_COLOR.2,
-                MENU_TEXT_COLOR.3,
-            )
-        };
+        let start_y = 110.0;
 
-        let start_text = if self.selected_option == MenuOption::Start {
-            "> START GAME <"
-        } else {
-            "  START GAME  "
-        };
+        let options = [
+            (MenuOption::Start, "START GAME".to_string()),
+            (MenuOption::Mode, format!("MODE: {}", self.mode.name())),
+            (MenuOption::Puzzle, self.puzzle_label()),
+            (MenuOption::Seed, format!("SEED: {}", self.seed)),
+            (MenuOption::Rules, format!("RULES: {}", self.get_rules().name())),
+            (MenuOption::Pieces, format!("PIECES: {}", self.get_piece_set().name)),
+            (MenuOption::Randomizer, format!("RANDOMIZER: {}", self.randomizer.name())),
+            (MenuOption::Preview, format!("NEXT PIECES: {}", self.preview_count)),
+            (MenuOption::BoardSize, format!("BOARD: {}", self.board_size.name())),
+            (MenuOption::LineGravity, format!("LINE GRAVITY: {}", self.line_gravity.name())),
+            (MenuOption::Quit, "QUIT GAME".to_string()),
+        ];
 
-        self.draw_text(canvas, start_text, center_x - 100.0, start_y, 32.0, start_color)?;
-
-        // Quit option
-        let quit_color = if self.selected_option == MenuOption::Quit {
-            Color::new(
-                MENU_HIGHLIGHT_COLOR.0,
-                MENU_HIGHLIGHT_COLOR.1,
-                MENU_HIGHLIGHT_COLOR.2,
-                MENU_HIGHLIGHT_COLOR.3,
-            )
-        } else {
-            Color::new(
-                MENU_TEXT_COLOR.0,
-                MENU_TEXT_COLOR.1,
-                MENU_TEXT_COLOR.2,
-                MENU_TEXT_COLOR.3,
-            )
-        };
+        for (i, (option, label)) in options.iter().enumerate() {
+            let selected = self.selected_option == *option;
+            let color = if selected {
+                Color::new(
+                    MENU_HIGHLIGHT_COLOR.0,
+                    MENU_HIGHLIGHT_COLOR.1,
+                    MENU_HIGHLIGHT_COLOR.2,
+                    MENU_HIGHLIGHT_COLOR.3,
+                )
+            } else {
+                Color::new(
+                    MENU_TEXT_COLOR.0,
+                    MENU_TEXT_COLOR.1,
+                    MENU_TEXT_COLOR.2,
+                    MENU_TEXT_COLOR.3,
+                )
+            };
 
-        let quit_text = if self.selected_option == MenuOption::Quit {
-            "> QUIT GAME <"
-        } else {
-            "  QUIT GAME  "
-        };
+            let text = if selected {
+                format!("> {} <", label)
+            } else {
+                format!("  {}  ", label)
+            };
 
-        self.draw_text(canvas, quit_text, center_x - 100.0, start_y + 60.0, 32.0, quit_color)?;
+            self.draw_text(canvas, &text, center_x - 100.0, start_y + i as f32 * 28.0, 24.0, color)?;
+        }
 
         Ok(())
     }
 
     fn draw_controls(&self, _ctx: &mut Context, canvas: &mut graphics::Canvas) -> GameResult {
-        let controls = vec![
+        let controls = [
             "Controls:",
             "Arrow Keys - Navigate",
             "Enter - Select",
+            "Left/Right - Change option",
+            "0-9 / Backspace - Edit seed",
             "ESC - Quit",
         ];
 
-        let start_y = 450.0;
+        let start_y = 440.0;
         for (i, control) in controls.iter().enumerate() {
             let y = start_y + (i as f32 * 25.0);
             let color = if i == 0 {
@@ -179,7 +220,16 @@ impl Menu {
 
     pub fn select_next(&mut self) {
         self.selected_option = match self.selected_option {
-            MenuOption::Start => MenuOption::Quit,
+            MenuOption::Start => MenuOption::Mode,
+            MenuOption::Mode => MenuOption::Puzzle,
+            MenuOption::Puzzle => MenuOption::Seed,
+            MenuOption::Seed => MenuOption::Rules,
+            MenuOption::Rules => MenuOption::Pieces,
+            MenuOption::Pieces => MenuOption::Randomizer,
+            MenuOption::Randomizer => MenuOption::Preview,
+            MenuOption::Preview => MenuOption::BoardSize,
+            MenuOption::BoardSize => MenuOption::LineGravity,
+            MenuOption::LineGravity => MenuOption::Quit,
             MenuOption::Quit => MenuOption::Start,
         };
     }
@@ -187,10 +237,131 @@ impl Menu {
     pub fn select_previous(&mut self) {
         self.selected_option = match self.selected_option {
             MenuOption::Start => MenuOption::Quit,
-            MenuOption::Quit => MenuOption::Start,
+            MenuOption::Mode => MenuOption::Start,
+            MenuOption::Puzzle => MenuOption::Mode,
+            MenuOption::Seed => MenuOption::Puzzle,
+            MenuOption::Rules => MenuOption::Seed,
+            MenuOption::Pieces => MenuOption::Rules,
+            MenuOption::Randomizer => MenuOption::Pieces,
+            MenuOption::Preview => MenuOption::Randomizer,
+            MenuOption::BoardSize => MenuOption::Preview,
+            MenuOption::LineGravity => MenuOption::BoardSize,
+            MenuOption::Quit => MenuOption::LineGravity,
         };
     }
 
+    pub fn get_mode(&self) -> GameMode {
+        self.mode
+    }
+
+    pub fn get_puzzle(&self) -> &Puzzle {
+        &self.puzzles[self.puzzle]
+    }
+
+    pub fn set_solved(&mut self, solved: BTreeSet<String>) {
+        self.solved = solved;
+    }
+
+    // e.g. "PUZZLE 3/5: STAIRS (SOLVED)"
+    fn puzzle_label(&self) -> String {
+        let puzzle = self.get_puzzle();
+        let solved = if self.solved.contains(&puzzle.id()) { " (SOLVED)" } else { "" };
+        format!("PUZZLE {}/{}: {}{}", self.puzzle + 1, self.puzzles.len(), puzzle.name, solved)
+    }
+
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    pub fn get_rules(&self) -> Rc<dyn RuleSet> {
+        self.rule_sets[self.rules].clone()
+    }
+
+    pub fn get_piece_set(&self) -> &PieceSet {
+        &self.piece_sets[self.piece_set]
+    }
+
+    pub fn get_randomizer(&self) -> RandomizerKind {
+        self.randomizer
+    }
+
+    pub fn get_preview_count(&self) -> usize {
+        self.preview_count
+    }
+
+    pub fn get_board_size(&self) -> BoardSize {
+        self.board_size
+    }
+
+    pub fn get_line_gravity(&self) -> LineGravity {
+        self.line_gravity
+    }
+
+    // Left/Right on the selected option
+    pub fn change_option(&mut self, forward: bool) {
+        match self.selected_option {
+            MenuOption::Mode => {
+                self.mode = if forward {
+                    self.mode.next()
+                } else {
+                    self.mode.previous()
+                };
+            }
+            MenuOption::Puzzle => {
+                self.puzzle = cycle_index(self.puzzle, self.puzzles.len(), forward);
+            }
+            MenuOption::Rules => {
+                self.rules = cycle_index(self.rules, self.rule_sets.len(), forward);
+            }
+            MenuOption::Pieces => {
+                self.piece_set = cycle_index(self.piece_set, self.piece_sets.len(), forward);
+                // Each set comes with the randomizer that suits it
+                self.randomizer = self.get_piece_set().randomizer;
+            }
+            MenuOption::Randomizer => {
+                self.randomizer = if forward {
+                    self.randomizer.next()
+                } else {
+                    self.randomizer.previous()
+                };
+            }
+            MenuOption::Preview => {
+                self.preview_count = if forward {
+                    (self.preview_count + 1).min(MAX_PREVIEW_COUNT)
+                } else {
+                    self.preview_count.saturating_sub(1)
+                };
+            }
+            MenuOption::BoardSize => {
+                self.board_size = if forward {
+                    self.board_size.next()
+                } else {
+                    self.board_size.previous()
+                };
+            }
+            MenuOption::LineGravity => {
+                self.line_gravity = if forward {
+                    self.line_gravity.next()
+                } else {
+                    self.line_gravity.previous()
+                };
+            }
+            _ => {}
+        }
+    }
+
+    pub fn push_seed_digit(&mut self, digit: u64) {
+        self.seed = self.seed.saturating_mul(10).saturating_add(digit);
+    }
+
+    pub fn pop_seed_digit(&mut self) {
+        self.seed /= 10;
+    }
+
     pub fn get_selected_option(&self) -> &MenuOption {
         &self.selected_option
     }
@@ -0,0 +1,97 @@
+use crate::cycle::cycle;
+use crate::config::{SPRINT_LINES, SPRINT_SPLIT_LINES};
+
+// What a game is played for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameMode {
+    // Endless, until the stack tops out
+    Marathon,
+    // Clear SPRINT_LINES lines as fast as possible
+    Sprint,
+    // Score as much as possible before the time limit
+    Ultra,
+    // Clear rows of garbage as fast as possible
+    Dig,
+    // Meet a preset board's objective with a fixed queue
+    Puzzle,
+    // Locked pieces fade out of view
+    Invisible,
+    // Only the rows around the active piece can be seen
+    Fog,
+    // Gravity ramps up to 20G, then lock delay and ARE shrink
+    Master,
+    // No game over and no speed up; totals carry over between sessions
+    Zen,
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 9] = [
+        GameMode::Marathon,
+        GameMode::Sprint,
+        GameMode::Ultra,
+        GameMode::Dig,
+        GameMode::Puzzle,
+        GameMode::Invisible,
+        GameMode::Fog,
+        GameMode::Master,
+        GameMode::Zen,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GameMode::Marathon => "MARATHON",
+            GameMode::Sprint => "SPRINT 40L",
+            GameMode::Ultra => "ULTRA",
+            GameMode::Dig => "DIG",
+            GameMode::Puzzle => "PUZZLE",
+            GameMode::Invisible => "INVISIBLE",
+            GameMode::Fog => "FOG",
+            GameMode::Master => "MASTER",
+            GameMode::Zen => "ZEN",
+        }
+    }
+
+    pub fn next(self) -> GameMode {
+        cycle(&Self::ALL, self, true)
+    }
+
+    pub fn previous(self) -> GameMode {
+        cycle(&Self::ALL, self, false)
+    }
+
+    // Lines that finish the game
+    pub fn line_goal(self) -> Option<u32> {
+        match self {
+            GameMode::Sprint => Some(SPRINT_LINES),
+            _ => None,
+        }
+    }
+
+    // A split time is taken every this many lines
+    pub fn split_lines(self) -> Option<u32> {
+        match self {
+            GameMode::Sprint => Some(SPRINT_SPLIT_LINES),
+            _ => None,
+        }
+    }
+
+    // Whether the game is played against the clock, for the best time
+    pub fn is_race(self) -> bool {
+        matches!(self, GameMode::Sprint | GameMode::Dig)
+    }
+
+    // Whether the game starts over a stack of garbage
+    pub fn has_garbage(self) -> bool {
+        self == GameMode::Dig
+    }
+
+    // Whether locked cells are hidden (the board still has them)
+    pub fn hides_stack(self) -> bool {
+        matches!(self, GameMode::Invisible | GameMode::Fog)
+    }
+
+    // Whether the game ends when its time limit runs out
+    pub fn is_timed(self) -> bool {
+        self == GameMode::Ultra
+    }
+}
@@ -0,0 +1,285 @@
+use std::fmt;
+use std::path::Path;
+use std::rc::Rc;
+
+use serde::Deserialize;
+
+use crate::config::{Rgba, BOARD_WIDTH, MAX_POLYOMINO_ORDER};
+use crate::polyomino::polyomino_set;
+use crate::randomizer::RandomizerKind;
+use crate::rotation::{KickTable, RotationSystem};
+
+const CLASSIC_JSON: &str = include_str!("../assets/pieces/classic.json");
+const PENTOMINO_JSON: &str = include_str!("../assets/pieces/pentomino.json");
+const TROMINO_JSON: &str = include_str!("../assets/pieces/tromino.json");
+const ARS_JSON: &str = include_str!("../assets/pieces/ars.json");
+
+// One piece shape: its matrices for each rotation state, color and rules
+#[derive(Debug, Clone, PartialEq)]
+pub struct PieceDef {
+    pub name: String,
+    pub color: Rgba,
+    // Rotation states in clockwise order; a piece with fewer than four
+    // states repeats them
+    pub rotations: Vec<Vec<Vec<bool>>>,
+    // Added to the set's spawn position
+    pub spawn_offset: (i32, i32),
+    pub kicks: KickTable,
+    // Whether placements of this piece are checked for T-spins
+    pub t_spin: bool,
+    // Relative frequency for the weighted randomizer
+    pub weight: u32,
+    // Randomizers that avoid bad openings never deal this piece first
+    pub avoid_first: bool,
+}
+
+impl PieceDef {
+    pub fn blocks(&self, rotation: usize) -> &Vec<Vec<bool>> {
+        &self.rotations[rotation % self.rotations.len()]
+    }
+
+    // Columns spanned by the blocks of the spawn state
+    pub fn width(&self) -> usize {
+        let spawn = &self.rotations[0];
+        let filled = |x: &usize| spawn.iter().any(|row| row[*x]);
+        let columns = spawn.first().map_or(0, |row| row.len());
+        let left = (0..columns).find(filled).unwrap_or(0);
+        let right = (0..columns).rfind(filled).unwrap_or(0);
+        right + 1 - left
+    }
+}
+
+// A named collection of pieces a game draws from
+#[derive(Debug, Clone, PartialEq)]
+pub struct PieceSet {
+    pub name: String,
+    pub pieces: Vec<Rc<PieceDef>>,
+    // Randomizer picked when the set is chosen in the menu
+    pub randomizer: RandomizerKind,
+    // Column of the left edge of the piece matrices at spawn on a standard
+    // width board; None centers each piece
+    pub spawn_column: Option<i32>,
+}
+
+#[derive(Debug)]
+pub enum PieceSetError {
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for PieceSetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PieceSetError::Io(error) => write!(f, "could not read piece set: {}", error),
+            PieceSetError::Parse(error) => write!(f, "could not parse piece set: {}", error),
+            PieceSetError::Invalid(reason) => write!(f, "invalid piece set: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for PieceSetError {}
+
+// Shapes are written as rows of '#' (block) and '.' (empty), e.g.
+// [".#..", "###.", "....", "...."]
+#[derive(Deserialize)]
+struct PieceSetFile {
+    name: String,
+    #[serde(default)]
+    randomizer: RandomizerKind,
+    #[serde(default)]
+    spawn_column: Option<i32>,
+    pieces: Vec<PieceFile>,
+}
+
+#[derive(Deserialize)]
+struct PieceFile {
+    name: String,
+    color: Rgba,
+    rotations: Vec<Vec<String>>,
+    #[serde(default)]
+    spawn_offset: (i32, i32),
+    #[serde(default)]
+    kicks: KickTable,
+    #[serde(default)]
+    t_spin: bool,
+    #[serde(default = "default_weight")]
+    weight: u32,
+    #[serde(default)]
+    avoid_first: bool,
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+impl Default for PieceSet {
+    fn default() -> Self {
+        Self::classic()
+    }
+}
+
+impl PieceSet {
+    // The seven standard tetrominoes
+    pub fn classic() -> Self {
+        Self::from_json(CLASSIC_JSON).expect("built-in classic piece set is valid")
+    }
+
+    pub fn pentomino() -> Self {
+        Self::from_json(PENTOMINO_JSON).expect("built-in pentomino piece set is valid")
+    }
+
+    pub fn tromino() -> Self {
+        Self::from_json(TROMINO_JSON).expect("built-in tromino piece set is valid")
+    }
+
+    // The seven tetrominoes with ARS rotation states: bottom-aligned, J, L
+    // and T flat side up at spawn, two states for I, S and Z
+    pub fn ars() -> Self {
+        Self::from_json(ARS_JSON).expect("built-in ARS piece set is valid")
+    }
+
+    // Classic and pentomino pieces together, pentominoes coming less often
+    pub fn mixed() -> Self {
+        let classic = Self::classic();
+        let mut pieces = classic.pieces;
+        pieces.extend(Self::pentomino().pieces);
+        PieceSet {
+            name: "MIXED".to_string(),
+            pieces,
+            randomizer: RandomizerKind::Weighted,
+            spawn_column: classic.spawn_column,
+        }
+    }
+
+    // The sets offered in the menu, in order: the hand-made ones, then every
+    // free polyomino of each order up to MAX_POLYOMINO_ORDER. TROMINO already
+    // holds both trominoes.
+    pub fn builtin() -> Vec<PieceSet> {
+        let mut sets = vec![Self::classic(), Self::pentomino(), Self::mixed(), Self::tromino()];
+        sets.extend((1..=MAX_POLYOMINO_ORDER).filter(|&n| n != 3).map(polyomino_set));
+        sets
+    }
+
+    // The pieces a game with the given rotation system draws from: ARS turns
+    // the classic set into its ARS version, anything else keeps the set
+    pub fn for_rotation(&self, rotation: RotationSystem) -> PieceSet {
+        if rotation == RotationSystem::Ars && *self == Self::classic() {
+            Self::ars()
+        } else {
+            self.clone()
+        }
+    }
+
+    // The pieces that fit across a board `board_width` columns wide at
+    // spawn; a set where none does is kept whole
+    pub fn fitting(&self, board_width: usize) -> PieceSet {
+        let pieces: Vec<_> = self.pieces.iter().filter(|piece| piece.width() <= board_width).cloned().collect();
+        if pieces.is_empty() {
+            return self.clone();
+        }
+        PieceSet { pieces, ..self.clone() }
+    }
+
+    // Spawn x for a piece matrix `piece_width` columns wide on a board
+    // `board_width` columns wide. Set columns keep their distance from the
+    // center on wider or narrower boards.
+    pub fn spawn_x(&self, piece_width: i32, board_width: i32) -> i32 {
+        match self.spawn_column {
+            Some(column) => column + (board_width - BOARD_WIDTH as i32) / 2,
+            None => (board_width - piece_width).max(0) / 2,
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, PieceSetError> {
+        let json = std::fs::read_to_string(path).map_err(PieceSetError::Io)?;
+        Self::from_json(&json)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, PieceSetError> {
+        let file: PieceSetFile = serde_json::from_str(json).map_err(PieceSetError::Parse)?;
+        if file.pieces.is_empty() {
+            return Err(PieceSetError::Invalid(format!("set {} has no pieces", file.name)));
+        }
+
+        let mut pieces = Vec::new();
+        for piece in file.pieces {
+            let rotations = piece
+                .rotations
+                .iter()
+                .map(|rows| parse_shape(&piece.name, rows))
+                .collect::<Result<Vec<_>, _>>()?;
+            if rotations.is_empty() {
+                return Err(PieceSetError::Invalid(format!("piece {} has no rotations", piece.name)));
+            }
+            let cells = |shape: &Vec<Vec<bool>>| shape.iter().flatten().filter(|&&cell| cell).count();
+            if rotations.iter().any(|shape| cells(shape) != cells(&rotations[0])) {
+                return Err(PieceSetError::Invalid(format!("piece {} changes size when it rotates", piece.name)));
+            }
+            pieces.push(Rc::new(PieceDef {
+                name: piece.name,
+                color: piece.color,
+                rotations,
+                spawn_offset: piece.spawn_offset,
+                kicks: piece.kicks,
+                t_spin: piece.t_spin,
+                weight: piece.weight,
+                avoid_first: piece.avoid_first,
+            }));
+        }
+
+        Ok(PieceSet {
+            name: file.name,
+            pieces,
+            randomizer: file.randomizer,
+            spawn_column: file.spawn_column,
+        })
+    }
+}
+
+fn parse_shape(name: &str, rows: &[String]) -> Result<Vec<Vec<bool>>, PieceSetError> {
+    let width = rows.first().map_or(0, |row| row.chars().count());
+    let mut shape = Vec::new();
+    for row in rows {
+        if row.chars().count() != width {
+            return Err(PieceSetError::Invalid(format!("piece {} has rows of different lengths", name)));
+        }
+        let cells = row
+            .chars()
+            .map(|c| match c {
+                '#' => Ok(true),
+                '.' => Ok(false),
+                _ => Err(PieceSetError::Invalid(format!("piece {} has unknown cell '{}'", name, c))),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        shape.push(cells);
+    }
+    if !shape.iter().flatten().any(|&cell| cell) {
+        return Err(PieceSetError::Invalid(format!("piece {} has an empty rotation", name)));
+    }
+    Ok(shape)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn built_in_pieces_keep_their_cells_in_every_rotation() {
+        for set in [PieceSet::classic(), PieceSet::pentomino(), PieceSet::tromino(), PieceSet::ars()] {
+            for piece in &set.pieces {
+                let count = |rotation| piece.blocks(rotation).iter().flatten().filter(|&&cell| cell).count();
+                assert!((0..4).all(|rotation| count(rotation) == count(0)), "{} {}", set.name, piece.name);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_pieces_that_change_size_when_they_rotate() {
+        let json = r#"{ "name": "BAD", "pieces": [
+            { "name": "Y", "color": [1, 1, 1, 1], "rotations": [[".##", ".#."], [".##", ".##"]] }
+        ] }"#;
+        let error = PieceSet::from_json(json).unwrap_err();
+        assert_eq!(error.to_string(), "invalid piece set: piece Y changes size when it rotates");
+    }
+}
@@ -0,0 +1,191 @@
+use std::collections::BTreeSet;
+use std::rc::Rc;
+
+use crate::config::Rgba;
+use crate::piece_set::{PieceDef, PieceSet};
+use crate::randomizer::RandomizerKind;
+use crate::rotation::KickTable;
+
+// Cells of a polyomino as (x, y), shifted so the smallest x and y are 0
+type Shape = Vec<(i32, i32)>;
+
+// Every free polyomino of order `n` (mirror images and rotations count as the
+// same shape), in a fixed order: 1, 1, 2, 5, 12 and 35 shapes for n = 1..6
+pub fn free_polyominoes(n: usize) -> Vec<Shape> {
+    if n == 0 {
+        return Vec::new();
+    }
+
+    let mut shapes: BTreeSet<Shape> = BTreeSet::new();
+    shapes.insert(vec![(0, 0)]);
+    for _ in 1..n {
+        let mut grown = BTreeSet::new();
+        for shape in &shapes {
+            for &(x, y) in shape {
+                for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+                    let cell = (x + dx, y + dy);
+                    if shape.contains(&cell) {
+                        continue;
+                    }
+                    let mut bigger = shape.clone();
+                    bigger.push(cell);
+                    grown.insert(canonical(&bigger));
+                }
+            }
+        }
+        shapes = grown;
+    }
+    shapes.into_iter().collect()
+}
+
+// All free polyominoes of order `n` as a piece set, named like "HEXOMINOES"
+pub fn polyomino_set(n: usize) -> PieceSet {
+    let shapes = free_polyominoes(n);
+    let count = shapes.len();
+    let pieces = shapes
+        .iter()
+        .enumerate()
+        .map(|(index, shape)| Rc::new(piece_def(format!("{}-{}", n, index + 1), shape, piece_color(index, count))))
+        .collect();
+
+    PieceSet {
+        name: set_name(n),
+        pieces,
+        randomizer: RandomizerKind::SevenBag,
+        spawn_column: None,
+    }
+}
+
+pub fn set_name(n: usize) -> String {
+    match n {
+        1 => "MONOMINOES".to_string(),
+        2 => "DOMINOES".to_string(),
+        3 => "TROMINOES".to_string(),
+        4 => "TETROMINOES".to_string(),
+        5 => "PENTOMINOES".to_string(),
+        6 => "HEXOMINOES".to_string(),
+        _ => format!("{}-OMINOES", n),
+    }
+}
+
+// Spawns flat side down in a square matrix, vertically centered like the
+// SRS pieces, so that rotating about the matrix center stays put
+fn piece_def(name: String, shape: &Shape, color: Rgba) -> PieceDef {
+    let spawn = (0..4)
+        .map(|turns| rotate_times(shape, turns))
+        .min_by_key(|rotation| {
+            let (width, height) = bounds(rotation);
+            let bottom_cells = rotation.iter().filter(|&&(_, y)| y == height - 1).count() as i32;
+            (height, -bottom_cells, -width)
+        })
+        .unwrap_or_else(|| shape.clone());
+
+    let (width, height) = bounds(&spawn);
+    let size = width.max(height);
+    let top = (size - height) / 2;
+    let left = (size - width) / 2;
+
+    let mut rotations = Vec::new();
+    let mut cells: Shape = spawn.iter().map(|&(x, y)| (x + left, y + top)).collect();
+    for _ in 0..4 {
+        let mut matrix = vec![vec![false; size as usize]; size as usize];
+        for &(x, y) in &cells {
+            matrix[y as usize][x as usize] = true;
+        }
+        rotations.push(matrix);
+        // Clockwise quarter turn inside the matrix
+        cells = cells.iter().map(|&(x, y)| (size - 1 - y, x)).collect();
+    }
+
+    let kicks = if rotations.iter().all(|rotation| *rotation == rotations[0]) {
+        KickTable::None
+    } else {
+        KickTable::Srs
+    };
+
+    PieceDef {
+        name,
+        color,
+        rotations,
+        spawn_offset: (0, 0),
+        kicks,
+        t_spin: false,
+        weight: 1,
+        avoid_first: false,
+    }
+}
+
+// Shades of purple spread over the set, neighbours differing in lightness
+fn piece_color(index: usize, count: usize) -> Rgba {
+    let t = if count > 1 { index as f32 / (count - 1) as f32 } else { 0.5 };
+    let light = ((index * 7) % count.max(1)) as f32 / count.max(1) as f32;
+    (0.35 + 0.6 * t, 0.05 + 0.6 * light, 1.0 - 0.35 * t, 1.0)
+}
+
+fn bounds(shape: &Shape) -> (i32, i32) {
+    let width = shape.iter().map(|&(x, _)| x).max().unwrap_or(0) + 1;
+    let height = shape.iter().map(|&(_, y)| y).max().unwrap_or(0) + 1;
+    (width, height)
+}
+
+fn normalize(shape: &Shape) -> Shape {
+    let min_x = shape.iter().map(|&(x, _)| x).min().unwrap_or(0);
+    let min_y = shape.iter().map(|&(_, y)| y).min().unwrap_or(0);
+    let mut cells: Shape = shape.iter().map(|&(x, y)| (x - min_x, y - min_y)).collect();
+    cells.sort_unstable();
+    cells
+}
+
+fn rotate_times(shape: &Shape, turns: usize) -> Shape {
+    let mut cells = shape.clone();
+    for _ in 0..turns {
+        cells = cells.iter().map(|&(x, y)| (-y, x)).collect();
+    }
+    normalize(&cells)
+}
+
+// The smallest of the eight rotations and reflections, so every orientation
+// of a shape maps to the same value
+fn canonical(shape: &Shape) -> Shape {
+    let mirrored: Shape = shape.iter().map(|&(x, y)| (-x, y)).collect();
+    (0..4)
+        .flat_map(|turns| [rotate_times(shape, turns), rotate_times(&mirrored, turns)])
+        .min()
+        .unwrap_or_else(|| normalize(shape))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_free_polyominoes() {
+        let counts: Vec<usize> = (1..=6).map(|n| free_polyominoes(n).len()).collect();
+        assert_eq!(counts, [1, 1, 2, 5, 12, 35]);
+    }
+
+    #[test]
+    fn pieces_keep_their_cells_in_every_rotation() {
+        for n in 1..=6 {
+            for piece in &polyomino_set(n).pieces {
+                assert_eq!(piece.rotations.len(), 4);
+                for rotation in &piece.rotations {
+                    assert_eq!(rotation.iter().flatten().filter(|&&cell| cell).count(), n, "{}", piece.name);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn spawns_flat_side_down() {
+        // The I tetromino lies flat, the T points up
+        let set = polyomino_set(4);
+        let flat = set.pieces.iter().find(|piece| piece.rotations[0].iter().any(|row| row.iter().all(|&cell| cell)));
+        assert!(flat.is_some());
+        for piece in &set.pieces {
+            let rows: Vec<usize> = piece.rotations[0].iter().map(|row| row.iter().filter(|&&cell| cell).count()).collect();
+            let last = rows.iter().rposition(|&count| count > 0).unwrap();
+            assert!(rows[last] >= rows[..last].iter().copied().max().unwrap_or(0), "{}", piece.name);
+        }
+    }
+}
@@ -0,0 +1,255 @@
+use std::fmt;
+use std::path::Path;
+use std::rc::Rc;
+
+use serde::Deserialize;
+
+use crate::board::{Board, Cell};
+use crate::config::{BOARD_HEIGHT, BOARD_WIDTH};
+use crate::piece_set::{PieceDef, PieceSet};
+use crate::scoring::{ClearAction, TSpin};
+
+const STARTER_JSON: &str = include_str!("../assets/puzzles/starter.json");
+
+// What a puzzle asks for, named in level files by "type", e.g.
+// { "type": "clear_lines", "lines": 4, "pieces": 3 }
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Objective {
+    // Clear `lines` lines placing at most `pieces` pieces
+    ClearLines { lines: u32, pieces: u32 },
+    // A full (not mini) T-spin clearing exactly `lines` lines
+    TSpin { lines: usize },
+    // Empty the board
+    PerfectClear,
+}
+
+impl Objective {
+    pub fn description(&self) -> String {
+        match *self {
+            Objective::ClearLines { lines, pieces } => format!("CLEAR {} LINES WITH {} PIECES", lines, pieces),
+            Objective::TSpin { lines } => match lines {
+                0 => "T-SPIN".to_string(),
+                1 => "T-SPIN SINGLE".to_string(),
+                2 => "T-SPIN DOUBLE".to_string(),
+                3 => "T-SPIN TRIPLE".to_string(),
+                _ => format!("T-SPIN CLEARING {} LINES", lines),
+            },
+            Objective::PerfectClear => "PERFECT CLEAR".to_string(),
+        }
+    }
+
+    // Judged after every placed piece: Some(true) once solved, Some(false)
+    // once it can no longer be solved, None while still open. Running out of
+    // pieces is checked by the game.
+    pub fn check(&self, lines_cleared: u32, pieces_placed: u32, action: &ClearAction) -> Option<bool> {
+        match *self {
+            Objective::ClearLines { lines, pieces } => {
+                if lines_cleared >= lines {
+                    Some(true)
+                } else if pieces_placed >= pieces {
+                    Some(false)
+                } else {
+                    None
+                }
+            }
+            Objective::TSpin { lines } => {
+                (action.t_spin == TSpin::Full && action.lines == lines).then_some(true)
+            }
+            Objective::PerfectClear => action.perfect_clear.then_some(true),
+        }
+    }
+}
+
+// A preset board, a fixed queue and an objective
+#[derive(Debug, Clone, PartialEq)]
+pub struct Puzzle {
+    pub name: String,
+    // Name of the pack it came from
+    pub pack: String,
+    // Bottom rows of the visible field, top to bottom
+    pub rows: Vec<Vec<Option<Cell>>>,
+    pub queue: Vec<Rc<PieceDef>>,
+    pub objective: Objective,
+}
+
+impl Puzzle {
+    // Unique across packs with different names; used to track solved puzzles
+    pub fn id(&self) -> String {
+        format!("{}/{}", self.pack, self.name)
+    }
+
+    // Fills the bottom of an empty standard size board
+    pub fn apply_to(&self, board: &mut Board) {
+        let top = board.rows() - self.rows.len();
+        for (y, row) in self.rows.iter().enumerate() {
+            board.grid[top + y] = row.clone();
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum PuzzleError {
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for PuzzleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PuzzleError::Io(error) => write!(f, "could not read puzzles: {}", error),
+            PuzzleError::Parse(error) => write!(f, "could not parse puzzles: {}", error),
+            PuzzleError::Invalid(reason) => write!(f, "invalid puzzle: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for PuzzleError {}
+
+// Boards are written as rows of '.' (empty), '#' (garbage) or a classic
+// piece name ('I', 'T', ...) for a block of that piece's color, e.g.
+// ["#########.", "#########."]. Queues list classic piece names.
+#[derive(Deserialize)]
+struct PackFile {
+    name: String,
+    puzzles: Vec<PuzzleFile>,
+}
+
+#[derive(Deserialize)]
+struct PuzzleFile {
+    name: String,
+    board: Vec<String>,
+    queue: Vec<String>,
+    objective: Objective,
+}
+
+// A named list of puzzles, played in order
+#[derive(Debug, Clone, PartialEq)]
+pub struct PuzzlePack {
+    pub name: String,
+    pub puzzles: Vec<Puzzle>,
+}
+
+impl PuzzlePack {
+    pub fn starter() -> Self {
+        Self::from_json(STARTER_JSON).expect("built-in starter puzzles are valid")
+    }
+
+    pub fn load(path: &Path) -> Result<Self, PuzzleError> {
+        let json = std::fs::read_to_string(path).map_err(PuzzleError::Io)?;
+        Self::from_json(&json)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, PuzzleError> {
+        let file: PackFile = serde_json::from_str(json).map_err(PuzzleError::Parse)?;
+        let pieces = PieceSet::classic().pieces;
+
+        let mut puzzles = Vec::new();
+        for puzzle in file.puzzles {
+            let rows = parse_board(&puzzle.name, &puzzle.board, &pieces)?;
+            let queue = puzzle
+                .queue
+                .iter()
+                .map(|name| {
+                    find_piece(&pieces, name)
+                        .ok_or_else(|| PuzzleError::Invalid(format!("{} has unknown piece {}", puzzle.name, name)))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            if queue.is_empty() {
+                return Err(PuzzleError::Invalid(format!("{} has no pieces", puzzle.name)));
+            }
+            puzzles.push(Puzzle {
+                name: puzzle.name,
+                pack: file.name.clone(),
+                rows,
+                queue,
+                objective: puzzle.objective,
+            });
+        }
+
+        Ok(PuzzlePack { name: file.name, puzzles })
+    }
+}
+
+fn find_piece(pieces: &[Rc<PieceDef>], name: &str) -> Option<Rc<PieceDef>> {
+    pieces.iter().find(|piece| piece.name == name).cloned()
+}
+
+// Leaves the two top visible rows free so the pieces can spawn
+fn parse_board(name: &str, rows: &[String], pieces: &[Rc<PieceDef>]) -> Result<Vec<Vec<Option<Cell>>>, PuzzleError> {
+    if rows.len() > BOARD_HEIGHT - 2 {
+        return Err(PuzzleError::Invalid(format!("{} has more than {} rows", name, BOARD_HEIGHT - 2)));
+    }
+    let mut board = Vec::new();
+    for row in rows {
+        if row.chars().count() != BOARD_WIDTH {
+            return Err(PuzzleError::Invalid(format!("{} has a row that is not {} wide", name, BOARD_WIDTH)));
+        }
+        let cells = row
+            .chars()
+            .map(|c| match c {
+                '.' => Ok(None),
+                '#' => Ok(Some(Cell::garbage())),
+                _ => find_piece(pieces, &c.to_string())
+                    .map(|piece| Some(Cell::piece(piece.color, 0.0)))
+                    .ok_or_else(|| PuzzleError::Invalid(format!("{} has unknown cell '{}'", name, c))),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        board.push(cells);
+    }
+    Ok(board)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scoring::ScoreState;
+
+    fn action(lines: usize, t_spin: TSpin, perfect_clear: bool) -> ClearAction {
+        ScoreState::new().score_lock(lines, t_spin, perfect_clear, 1)
+    }
+
+    #[test]
+    fn judges_line_objectives_by_pieces_used() {
+        let objective = Objective::ClearLines { lines: 4, pieces: 3 };
+        let none = action(0, TSpin::None, false);
+        assert_eq!(objective.check(2, 2, &none), None);
+        assert_eq!(objective.check(4, 3, &none), Some(true));
+        assert_eq!(objective.check(3, 3, &none), Some(false));
+    }
+
+    #[test]
+    fn t_spin_objectives_need_a_full_spin_of_the_right_size() {
+        let objective = Objective::TSpin { lines: 2 };
+        assert_eq!(objective.check(2, 1, &action(2, TSpin::Full, false)), Some(true));
+        assert_eq!(objective.check(2, 1, &action(2, TSpin::Mini, false)), None);
+        assert_eq!(objective.check(1, 1, &action(1, TSpin::Full, false)), None);
+        assert_eq!(Objective::PerfectClear.check(2, 1, &action(2, TSpin::None, true)), Some(true));
+    }
+
+    #[test]
+    fn loads_the_starter_pack() {
+        let pack = PuzzlePack::starter();
+        assert_eq!(pack.puzzles.len(), 5);
+        let mut board = Board::default();
+        pack.puzzles[0].apply_to(&mut board);
+        assert!(!board.is_empty());
+    }
+
+    fn pack_with_board(row: &str) -> String {
+        format!(
+            r#"{{ "name": "BAD", "puzzles": [{{ "name": "P", "board": ["{}"], "queue": ["I"], "objective": {{ "type": "perfect_clear" }} }}] }}"#,
+            row
+        )
+    }
+
+    #[test]
+    fn rejects_bad_boards() {
+        assert!(PuzzlePack::from_json(&pack_with_board("#########.")).is_ok());
+        let too_wide = pack_with_board("###########");
+        assert!(matches!(PuzzlePack::from_json(&too_wide), Err(PuzzleError::Invalid(_))));
+        let unknown_cell = pack_with_board("#########X");
+        assert!(matches!(PuzzlePack::from_json(&unknown_cell), Err(PuzzleError::Invalid(_))));
+    }
+}
@@ -0,0 +1,217 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use serde::Deserialize;
+
+use crate::cycle::cycle;
+use crate::piece_set::PieceSet;
+
+// Decides which piece comes next, as an index into the game's piece set.
+// The RNG is owned by `Game` and passed in, so every strategy stays
+// deterministic for a given seed.
+pub trait Randomizer {
+    fn next(&mut self, rng: &mut StdRng) -> usize;
+}
+
+// Named in piece set files as "random", "bag", "double_bag", "history" and "weighted"
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RandomizerKind {
+    #[default]
+    Random,
+    #[serde(rename = "bag")]
+    SevenBag,
+    #[serde(rename = "double_bag")]
+    FourteenBag,
+    History,
+    Weighted,
+}
+
+impl RandomizerKind {
+    pub const ALL: [RandomizerKind; 5] = [
+        RandomizerKind::Random,
+        RandomizerKind::SevenBag,
+        RandomizerKind::FourteenBag,
+        RandomizerKind::History,
+        RandomizerKind::Weighted,
+    ];
+
+    // Bags hold one (or two) of every piece in the set, so with the classic
+    // set they are the usual 7-bag and 14-bag
+    pub fn create(self, set: &PieceSet) -> Box<dyn Randomizer> {
+        let count = set.pieces.len();
+        match self {
+            RandomizerKind::Random => Box::new(PureRandom::new(count)),
+            RandomizerKind::SevenBag => Box::new(Bag::new(count, 1)),
+            RandomizerKind::FourteenBag => Box::new(Bag::new(count, 2)),
+            RandomizerKind::History => {
+                let avoid_first = (0..count).filter(|&i| set.pieces[i].avoid_first).collect();
+                Box::new(History::new(count, avoid_first, 4, 6))
+            }
+            RandomizerKind::Weighted => {
+                let weights: Vec<u32> = set.pieces.iter().map(|piece| piece.weight).collect();
+                Box::new(Bag::weighted(&weights))
+            }
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RandomizerKind::Random => "RANDOM",
+            RandomizerKind::SevenBag => "BAG",
+            RandomizerKind::FourteenBag => "DOUBLE BAG",
+            RandomizerKind::History => "HISTORY",
+            RandomizerKind::Weighted => "WEIGHTED",
+        }
+    }
+
+    pub fn next(self) -> RandomizerKind {
+        cycle(&Self::ALL, self, true)
+    }
+
+    pub fn previous(self) -> RandomizerKind {
+        cycle(&Self::ALL, self, false)
+    }
+}
+
+// Uniform draw from the pool, every time
+pub struct PureRandom {
+    count: usize,
+}
+
+impl PureRandom {
+    pub fn new(count: usize) -> Self {
+        PureRandom { count }
+    }
+}
+
+impl Randomizer for PureRandom {
+    fn next(&mut self, rng: &mut StdRng) -> usize {
+        rng.gen_range(0..self.count)
+    }
+}
+
+// Shuffles a bag with a fixed number of copies of each piece and deals it out
+// before refilling. 7-bag and 14-bag bound droughts; weights favour some pieces.
+pub struct Bag {
+    contents: Vec<usize>,
+    bag: Vec<usize>,
+}
+
+impl Bag {
+    pub fn new(count: usize, copies: u32) -> Self {
+        Self::weighted(&vec![copies; count])
+    }
+
+    // `weights[i]` copies of piece `i` per bag. Pieces with weight 0 never come.
+    pub fn weighted(weights: &[u32]) -> Self {
+        let mut contents = Vec::new();
+        for (piece, &copies) in weights.iter().enumerate() {
+            contents.extend(std::iter::repeat_n(piece, copies as usize));
+        }
+        if contents.is_empty() {
+            contents = (0..weights.len().max(1)).collect();
+        }
+        Bag {
+            contents,
+            bag: Vec::new(),
+        }
+    }
+}
+
+impl Randomizer for Bag {
+    fn next(&mut self, rng: &mut StdRng) -> usize {
+        if self.bag.is_empty() {
+            self.bag = self.contents.clone();
+            self.bag.shuffle(rng);
+        }
+        // `contents` is never empty, so the refill above always gives a piece
+        self.bag.pop().unwrap_or(self.contents[0])
+    }
+}
+
+// TGM-style: reroll up to `rolls` times while the piece is in the recent
+// history. The first piece avoids the `avoid_first` pieces (S, Z and O in
+// the classic set), which also fill the history at the start.
+pub struct History {
+    count: usize,
+    avoid_first: Vec<usize>,
+    history: Vec<usize>,
+    size: usize,
+    rolls: usize,
+    first: bool,
+}
+
+impl History {
+    pub fn new(count: usize, avoid_first: Vec<usize>, size: usize, rolls: usize) -> Self {
+        let history = avoid_first.iter().copied().cycle().take(size).collect();
+        History {
+            count,
+            avoid_first,
+            history,
+            size,
+            rolls,
+            first: true,
+        }
+    }
+
+    fn roll(&self, rng: &mut StdRng) -> usize {
+        rng.gen_range(0..self.count)
+    }
+}
+
+impl Randomizer for History {
+    fn next(&mut self, rng: &mut StdRng) -> usize {
+        let mut piece = self.roll(rng);
+        if self.first {
+            self.first = false;
+            for _ in 0..self.rolls {
+                if !self.avoid_first.contains(&piece) {
+                    break;
+                }
+                piece = self.roll(rng);
+            }
+        } else {
+            for _ in 0..self.rolls {
+                if !self.history.contains(&piece) {
+                    break;
+                }
+                piece = self.roll(rng);
+            }
+        }
+
+        self.history.push(piece);
+        if self.history.len() > self.size {
+            self.history.remove(0);
+        }
+        piece
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn bags_deal_every_piece_once_per_bag() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut bag = RandomizerKind::SevenBag.create(&PieceSet::classic());
+        for _ in 0..4 {
+            let mut drawn: Vec<usize> = (0..7).map(|_| bag.next(&mut rng)).collect();
+            drawn.sort_unstable();
+            assert_eq!(drawn, [0, 1, 2, 3, 4, 5, 6]);
+        }
+    }
+
+    #[test]
+    fn history_never_deals_an_avoided_piece_first() {
+        let set = PieceSet::classic();
+        for seed in 0..50 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let first = RandomizerKind::History.create(&set).next(&mut rng);
+            assert!(!set.pieces[first].avoid_first);
+        }
+    }
+}
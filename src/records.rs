@@ -0,0 +1,128 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+// Personal bests kept between sessions, as JSON
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Records {
+    // Best sprint time in seconds, by piece set name
+    #[serde(default)]
+    pub sprint: BTreeMap<String, f32>,
+    // Best ultra score, by piece set name and time limit, e.g. "CLASSIC 120s"
+    #[serde(default)]
+    pub ultra: BTreeMap<String, u32>,
+    // Best dig time, by piece set name and garbage rows, e.g. "CLASSIC 10 rows"
+    #[serde(default)]
+    pub dig: BTreeMap<String, f32>,
+    // Ids of the puzzles solved at least once
+    #[serde(default)]
+    pub solved: BTreeSet<String>,
+    #[serde(default)]
+    pub zen: ZenTotals,
+}
+
+// Where the zen board left off
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ZenTotals {
+    pub score: u32,
+    pub lines: u32,
+}
+
+impl Records {
+    // A missing or unreadable file gives empty records
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, json)
+    }
+
+    pub fn sprint_best(&self, piece_set: &str) -> Option<f32> {
+        self.sprint.get(piece_set).copied()
+    }
+
+    // Keeps `time` if it beats the best for the set; true when it does
+    pub fn submit_sprint(&mut self, piece_set: &str, time: f32) -> bool {
+        submit_time(&mut self.sprint, piece_set.to_string(), time)
+    }
+
+    pub fn dig_best(&self, piece_set: &str, garbage_rows: usize) -> Option<f32> {
+        self.dig.get(&dig_key(piece_set, garbage_rows)).copied()
+    }
+
+    // Keeps `time` if it beats the best for the set and garbage rows; true
+    // when it does
+    pub fn submit_dig(&mut self, piece_set: &str, garbage_rows: usize, time: f32) -> bool {
+        submit_time(&mut self.dig, dig_key(piece_set, garbage_rows), time)
+    }
+
+    // True the first time a puzzle is solved
+    pub fn mark_solved(&mut self, puzzle_id: &str) -> bool {
+        self.solved.insert(puzzle_id.to_string())
+    }
+
+    pub fn ultra_best(&self, piece_set: &str, time_limit: f32) -> Option<u32> {
+        self.ultra.get(&ultra_key(piece_set, time_limit)).copied()
+    }
+
+    // Keeps `score` if it beats the best for the set and time limit; true
+    // when it does
+    pub fn submit_ultra(&mut self, piece_set: &str, time_limit: f32, score: u32) -> bool {
+        let improved = self.ultra_best(piece_set, time_limit).is_none_or(|best| score > best);
+        if improved {
+            self.ultra.insert(ultra_key(piece_set, time_limit), score);
+        }
+        improved
+    }
+}
+
+fn submit_time(bests: &mut BTreeMap<String, f32>, key: String, time: f32) -> bool {
+    let improved = bests.get(&key).is_none_or(|&best| time < best);
+    if improved {
+        bests.insert(key, time);
+    }
+    improved
+}
+
+fn dig_key(piece_set: &str, garbage_rows: usize) -> String {
+    format!("{} {} rows", piece_set, garbage_rows)
+}
+
+fn ultra_key(piece_set: &str, time_limit: f32) -> String {
+    format!("{} {}s", piece_set, time_limit.round() as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_best_per_set() {
+        let mut records = Records::default();
+        assert!(records.submit_sprint("CLASSIC", 80.0));
+        assert!(!records.submit_sprint("CLASSIC", 90.0));
+        assert!(records.submit_sprint("CLASSIC", 70.0));
+        assert!(records.submit_sprint("PENTOMINO", 95.0));
+        assert_eq!(records.sprint_best("CLASSIC"), Some(70.0));
+
+        assert!(records.submit_ultra("CLASSIC", 120.0, 5000));
+        assert!(!records.submit_ultra("CLASSIC", 120.0, 4000));
+        assert_eq!(records.ultra_best("CLASSIC", 180.0), None);
+        assert!(records.submit_dig("CLASSIC", 10, 40.0));
+        assert_eq!(records.dig_best("CLASSIC", 5), None);
+    }
+
+    #[test]
+    fn reads_files_missing_fields() {
+        let records: Records = serde_json::from_str(r#"{ "sprint": { "CLASSIC": 61.5 } }"#).unwrap();
+        assert_eq!(records.sprint_best("CLASSIC"), Some(61.5));
+        assert_eq!(records.zen, ZenTotals::default());
+        assert!(records.solved.is_empty());
+    }
+}
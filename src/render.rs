@@ -0,0 +1,245 @@
+use ggez::graphics::{self, Color, DrawMode, DrawParam, Mesh, Rect};
+use ggez::mint::Point2;
+use ggez::{Context, GameResult};
+
+use purple_box_destruction::board::{Board, CellKind};
+use purple_box_destruction::config::{
+    Rgba, GRID_COLOR, BORDER_COLOR, GHOST_COLOR, LINE_FLASH_COLOR, BLOCK_SIZE, BOARD_OFFSET_X, BOARD_OFFSET_Y,
+    WINDOW_HEIGHT, HUD_X,
+};
+use purple_box_destruction::game::Game;
+use purple_box_destruction::tetromino::Tetromino;
+
+pub fn to_color(rgba: Rgba) -> Color {
+    Color::new(rgba.0, rgba.1, rgba.2, rgba.3)
+}
+
+// Room kept right of the board for the next queue column
+const QUEUE_COLUMN_WIDTH: f32 = 100.0;
+
+// Draws the playfield of a headless `Game` at a given position and scale.
+// Only the visible rows are drawn; the hidden buffer above them is clipped.
+pub struct BoardRenderer {
+    pub block_size: f32,
+    pub offset_x: f32,
+    pub offset_y: f32,
+    pub board_width: usize,
+    pub buffer_rows: usize,
+}
+
+impl BoardRenderer {
+    // Largest block size (up to BLOCK_SIZE) that fits the visible board
+    // between the window edge and the HUD
+    pub fn fit(board: &Board) -> Self {
+        let available_width = HUD_X - BOARD_OFFSET_X - QUEUE_COLUMN_WIDTH;
+        let available_height = WINDOW_HEIGHT - 2.0 * BOARD_OFFSET_Y;
+        let block_size = BLOCK_SIZE
+            .min(available_width / board.width as f32)
+            .min(available_height / board.height as f32)
+            .floor();
+        BoardRenderer {
+            block_size,
+            offset_x: BOARD_OFFSET_X,
+            offset_y: BOARD_OFFSET_Y,
+            board_width: board.width,
+            buffer_rows: board.buffer_rows,
+        }
+    }
+
+    // X position of the first column right of the board
+    pub fn right_edge(&self) -> f32 {
+        self.offset_x + self.board_width as f32 * self.block_size
+    }
+
+    // Screen y of board row 0 (the top of the hidden buffer)
+    pub fn origin_y(&self) -> f32 {
+        self.offset_y - self.buffer_rows as f32 * self.block_size
+    }
+
+    // Screen rectangle of a board cell, or None for hidden buffer rows
+    pub fn cell_rect(&self, x: i32, y: i32) -> Option<Rect> {
+        if y < self.buffer_rows as i32 {
+            return None;
+        }
+        Some(Rect::new(
+            x as f32 * self.block_size + self.offset_x,
+            y as f32 * self.block_size + self.origin_y(),
+            self.block_size,
+            self.block_size,
+        ))
+    }
+
+    pub fn draw(&self, ctx: &mut Context, canvas: &mut graphics::Canvas, game: &Game) -> GameResult {
+        // Draw board
+        self.draw_board(ctx, canvas, game)?;
+
+        // Flash full rows until they collapse
+        if let Some((rows, progress)) = game.clearing_rows() {
+            self.draw_line_flash(ctx, canvas, rows, progress)?;
+        }
+
+        // Draw current tetromino
+        if let Some(ref tetromino) = game.current_tetromino {
+            // Draw ghost piece, unless it would give a hidden stack away
+            if !game.mode.hides_stack() {
+                self.draw_ghost(ctx, canvas, &game.board, tetromino)?;
+            }
+            
+            // Draw current tetromino, dimming it as the lock delay runs out
+            let brightness = 1.0 - 0.5 * game.lock_progress();
+            self.draw_piece(ctx, canvas, tetromino, brightness)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn draw_board(&self, ctx: &mut Context, canvas: &mut graphics::Canvas, game: &Game) -> GameResult {
+        let board = &game.board;
+        // Draw background grid
+        for y in board.buffer_rows..board.rows() {
+            for x in 0..board.width {
+                let Some(rect) = self.cell_rect(x as i32, y as i32) else {
+                    continue;
+                };
+                
+                // Draw grid lines
+                let grid_mesh = Mesh::new_rectangle(ctx, DrawMode::stroke(1.0), rect, to_color(GRID_COLOR))?;
+                canvas.draw(&grid_mesh, DrawParam::default());
+                
+                // Draw placed blocks, faded in modes that hide them
+                if let Some(cell) = board.grid[y][x] {
+                    let visibility = game.cell_visibility(x, y);
+                    if visibility <= 0.0 {
+                        continue;
+                    }
+                    let mut color = to_color(cell.color);
+                    color.a *= visibility;
+                    let mut border = to_color(BORDER_COLOR);
+                    border.a *= visibility;
+                    match cell.kind {
+                        CellKind::Piece => draw_block(ctx, canvas, rect, color, border)?,
+                        CellKind::Garbage => draw_garbage_block(ctx, canvas, rect, color)?,
+                    }
+                }
+            }
+        }
+        
+        // Draw board border
+        let border_rect = Rect::new(
+            self.offset_x - 2.0,
+            self.offset_y - 2.0,
+            board.width as f32 * self.block_size + 4.0,
+            board.height as f32 * self.block_size + 4.0,
+        );
+        let border_mesh = Mesh::new_rectangle(ctx, DrawMode::stroke(4.0), border_rect, to_color(BORDER_COLOR))?;
+        canvas.draw(&border_mesh, DrawParam::default());
+        
+        Ok(())
+    }
+
+    // Rows blink a few times while fading out over the line clear delay
+    pub fn draw_line_flash(&self, ctx: &mut Context, canvas: &mut graphics::Canvas, rows: &[usize], progress: f32) -> GameResult {
+        let blink_on = ((progress * 6.0) as u32).is_multiple_of(2);
+        let mut color = to_color(LINE_FLASH_COLOR);
+        color.a *= (1.0 - progress) * if blink_on { 1.0 } else { 0.4 };
+
+        for &y in rows {
+            let Some(left) = self.cell_rect(0, y as i32) else {
+                continue;
+            };
+            let rect = Rect::new(left.x, left.y, self.board_width as f32 * self.block_size, self.block_size);
+            let mesh = Mesh::new_rectangle(ctx, DrawMode::fill(), rect, color)?;
+            canvas.draw(&mesh, DrawParam::default());
+        }
+
+        Ok(())
+    }
+
+    pub fn draw_ghost(&self, ctx: &mut Context, canvas: &mut graphics::Canvas, board: &Board, tetromino: &Tetromino) -> GameResult {
+        let (ghost_x, ghost_y) = board.get_ghost_position(tetromino);
+        
+        for (y, row) in tetromino.blocks.iter().enumerate() {
+            for (x, &block) in row.iter().enumerate() {
+                if !block {
+                    continue;
+                }
+                if let Some(rect) = self.cell_rect(ghost_x + x as i32, ghost_y + y as i32) {
+                    let mesh = Mesh::new_rectangle(ctx, DrawMode::stroke(2.0), rect, to_color(GHOST_COLOR))?;
+                    canvas.draw(&mesh, DrawParam::default());
+                }
+            }
+        }
+        
+        Ok(())
+    }
+
+    // Draws a piece at its board position. `brightness` scales its color.
+    pub fn draw_piece(&self, ctx: &mut Context, canvas: &mut graphics::Canvas, tetromino: &Tetromino, brightness: f32) -> GameResult {
+        let base = to_color(tetromino.get_color());
+        let color = Color::new(base.r * brightness, base.g * brightness, base.b * brightness, base.a);
+        
+        for (y, row) in tetromino.blocks.iter().enumerate() {
+            for (x, &block) in row.iter().enumerate() {
+                if !block {
+                    continue;
+                }
+                if let Some(rect) = self.cell_rect(tetromino.x + x as i32, tetromino.y + y as i32) {
+                    draw_block(ctx, canvas, rect, color, PIECE_BORDER_COLOR)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+const PIECE_BORDER_COLOR: Color = Color::new(0.9, 0.5, 1.0, 1.0); // Light purple border
+
+// Garbage is flat and crossed out, so it never reads as a piece
+fn draw_garbage_block(ctx: &mut Context, canvas: &mut graphics::Canvas, rect: Rect, color: Color) -> GameResult {
+    let mesh = Mesh::new_rectangle(ctx, DrawMode::fill(), rect, color)?;
+    canvas.draw(&mesh, DrawParam::default());
+
+    let dark = Color::new(color.r * 0.6, color.g * 0.6, color.b * 0.6, color.a);
+    let inset = rect.w * 0.2;
+    let cross = [
+        [Point2 { x: rect.x + inset, y: rect.y + inset }, Point2 { x: rect.right() - inset, y: rect.bottom() - inset }],
+        [Point2 { x: rect.right() - inset, y: rect.y + inset }, Point2 { x: rect.x + inset, y: rect.bottom() - inset }],
+    ];
+    for points in cross {
+        let line = Mesh::new_line(ctx, &points, 2.0, dark)?;
+        canvas.draw(&line, DrawParam::default());
+    }
+
+    let border_mesh = Mesh::new_rectangle(ctx, DrawMode::stroke(1.0), rect, dark)?;
+    canvas.draw(&border_mesh, DrawParam::default());
+    Ok(())
+}
+
+fn draw_block(ctx: &mut Context, canvas: &mut graphics::Canvas, rect: Rect, color: Color, border_color: Color) -> GameResult {
+    let mesh = Mesh::new_rectangle(ctx, DrawMode::fill(), rect, color)?;
+    canvas.draw(&mesh, DrawParam::default());
+    
+    let border_mesh = Mesh::new_rectangle(ctx, DrawMode::stroke(2.0), rect, border_color)?;
+    canvas.draw(&border_mesh, DrawParam::default());
+    Ok(())
+}
+
+// Draws a piece outside the board (previews), with its matrix at the offset
+pub fn draw_tetromino(ctx: &mut Context, canvas: &mut graphics::Canvas, tetromino: &Tetromino, block_size: f32, offset_x: f32, offset_y: f32) -> GameResult {
+    let color = to_color(tetromino.get_color());
+    
+    for (y, row) in tetromino.blocks.iter().enumerate() {
+        for (x, &block) in row.iter().enumerate() {
+            if block {
+                let rect = Rect::new(
+                    (tetromino.x + x as i32) as f32 * block_size + offset_x,
+                    (tetromino.y + y as i32) as f32 * block_size + offset_y,
+                    block_size,
+                    block_size,
+                );
+                draw_block(ctx, canvas, rect, color, PIECE_BORDER_COLOR)?;
+            }
+        }
+    }
+    Ok(())
+}
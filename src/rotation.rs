@@ -0,0 +1,168 @@
+use serde::Deserialize;
+
+// Wall kick data for the rotation systems rule sets can use.
+//
+// Rotation states are 0 (spawn), 1 (R, clockwise), 2 (180) and 3 (L).
+// Offsets are written as in the SRS guideline, with +y pointing UP, so they
+// can be checked against the reference tables; callers flip y for the board.
+// The first entry is always (0, 0): the plain rotation.
+
+const NO_KICKS: [(i32, i32); 1] = [(0, 0)];
+
+// Arika-style: the plain rotation, then one cell right, then one left
+const ARS_KICKS: [(i32, i32); 3] = [(0, 0), (1, 0), (-1, 0)];
+
+const JLSTZ_KICKS: [[(i32, i32); 5]; 8] = [
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)], // 0 -> R
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],     // R -> 0
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],     // R -> 2
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)], // 2 -> R
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],    // 2 -> L
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],  // L -> 2
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],  // L -> 0
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],    // 0 -> L
+];
+
+const I_KICKS: [[(i32, i32); 5]; 8] = [
+    [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)], // 0 -> R
+    [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)], // R -> 0
+    [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)], // R -> 2
+    [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)], // 2 -> R
+    [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)], // 2 -> L
+    [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)], // L -> 2
+    [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)], // L -> 0
+    [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)], // 0 -> L
+];
+
+// 180 degree turns are not part of the original SRS; these are the widely
+// used SRS+ offsets, shared by every piece
+const HALF_TURN_KICKS: [[(i32, i32); 6]; 4] = [
+    [(0, 0), (0, 1), (1, 1), (-1, 1), (1, 0), (-1, 0)],     // 0 -> 2
+    [(0, 0), (1, 0), (1, 2), (1, 1), (0, 2), (0, 1)],       // R -> L
+    [(0, 0), (0, -1), (-1, -1), (1, -1), (-1, 0), (1, 0)],  // 2 -> 0
+    [(0, 0), (-1, 0), (-1, 2), (-1, 1), (0, 2), (0, 1)],    // L -> R
+];
+
+// Which wall kick table a piece uses, as named in piece set files
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KickTable {
+    // Rotate in place or not at all
+    None,
+    // The J/L/S/T/Z table, also used by pentominoes
+    #[default]
+    Srs,
+    SrsI,
+    // Arika-style kicks, for the S and Z of the ARS set
+    Ars,
+    // Arika-style kicks that are skipped when the rotation is blocked in the
+    // center column first, for the J, L and T of the ARS set
+    ArsCenter,
+}
+
+// How a rule set resolves rotations that do not fit in place
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RotationSystem {
+    // Super Rotation System, with SRS+ 180 degree kicks
+    Srs,
+    // Arika Rotation System, as in TGM: the classic set is swapped for its
+    // ARS shapes (see `PieceSet::for_rotation`); pieces of other sets keep
+    // their shapes and get the same right-then-left kicks
+    Ars,
+    // Rotate in place or not at all
+    None,
+}
+
+impl RotationSystem {
+    pub fn kicks(self, table: KickTable, from: usize, to: usize) -> &'static [(i32, i32)] {
+        match self {
+            RotationSystem::Srs => kicks(table, from, to),
+            RotationSystem::Ars => match table {
+                KickTable::Srs | KickTable::Ars | KickTable::ArsCenter => &ARS_KICKS,
+                KickTable::SrsI | KickTable::None => &NO_KICKS,
+            },
+            RotationSystem::None => &NO_KICKS,
+        }
+    }
+}
+
+// Index into the 8-row tables for a quarter turn between two states
+fn transition_index(from: usize, to: usize) -> Option<usize> {
+    match (from % 4, to % 4) {
+        (0, 1) => Some(0),
+        (1, 0) => Some(1),
+        (1, 2) => Some(2),
+        (2, 1) => Some(3),
+        (2, 3) => Some(4),
+        (3, 2) => Some(5),
+        (3, 0) => Some(6),
+        (0, 3) => Some(7),
+        _ => None,
+    }
+}
+
+// SRS offsets to try, in order, when rotating a piece with the given table
+// from one state to another
+pub fn kicks(table: KickTable, from: usize, to: usize) -> &'static [(i32, i32)] {
+    match table {
+        KickTable::None => return &NO_KICKS,
+        KickTable::Ars | KickTable::ArsCenter => return &ARS_KICKS,
+        KickTable::Srs | KickTable::SrsI => {}
+    }
+
+    if (from + 2) % 4 == to % 4 {
+        return &HALF_TURN_KICKS[from % 4];
+    }
+
+    match (transition_index(from, to), table) {
+        (Some(index), KickTable::SrsI) => &I_KICKS[index],
+        (Some(index), _) => &JLSTZ_KICKS[index],
+        (None, _) => &NO_KICKS,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tries_the_plain_rotation_first() {
+        let systems = [RotationSystem::Srs, RotationSystem::Ars, RotationSystem::None];
+        let tables = [KickTable::None, KickTable::Srs, KickTable::SrsI, KickTable::Ars, KickTable::ArsCenter];
+        for system in systems {
+            for table in tables {
+                for from in 0..4 {
+                    for to in [from + 1, from + 2, from + 3] {
+                        assert_eq!(system.kicks(table, from, to % 4)[0], (0, 0));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn uses_the_srs_tables_for_quarter_turns() {
+        assert_eq!(kicks(KickTable::Srs, 0, 1), &[(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)]);
+        assert_eq!(kicks(KickTable::Srs, 3, 0), &[(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)]);
+        assert_eq!(kicks(KickTable::SrsI, 0, 1), &[(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)]);
+        assert_eq!(kicks(KickTable::SrsI, 1, 2), &[(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)]);
+    }
+
+    #[test]
+    fn every_piece_shares_the_half_turn_table() {
+        for table in [KickTable::Srs, KickTable::SrsI] {
+            assert_eq!(kicks(table, 0, 2), &HALF_TURN_KICKS[0]);
+            assert_eq!(kicks(table, 3, 1), &HALF_TURN_KICKS[3]);
+        }
+        assert_eq!(kicks(KickTable::None, 0, 2), &NO_KICKS);
+    }
+
+    #[test]
+    fn ars_kicks_right_then_left_except_for_i() {
+        assert_eq!(RotationSystem::Ars.kicks(KickTable::ArsCenter, 0, 1), &ARS_KICKS);
+        assert_eq!(RotationSystem::Ars.kicks(KickTable::Srs, 2, 1), &ARS_KICKS);
+        assert_eq!(RotationSystem::Ars.kicks(KickTable::SrsI, 0, 1), &NO_KICKS);
+        assert_eq!(RotationSystem::None.kicks(KickTable::Srs, 0, 1), &NO_KICKS);
+    }
+}
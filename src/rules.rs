@@ -0,0 +1,476 @@
+use std::fmt;
+use std::path::Path;
+use std::rc::Rc;
+
+use serde::Deserialize;
+
+use crate::config::{
+    FRAMES_PER_SECOND, INITIAL_DROP_INTERVAL, MIN_DROP_INTERVAL, LEVEL_SPEED_INCREASE,
+    LOCK_DELAY, MAX_LOCK_RESETS, LINES_PER_LEVEL, SOFT_DROP_FACTOR, INSTANT_GRAVITY,
+};
+use crate::input::Handling;
+use crate::rotation::RotationSystem;
+use crate::scoring::{ClearAction, ScoreState, TSpin};
+use crate::timing::{DelayStep, Delays};
+
+// How a grounded piece gets more time before it locks
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LockReset {
+    // Moves and rotations restart the timer, at most `max` times per row reached
+    Move { max: u32 },
+    // Only reaching a new lowest row restarts it
+    Step,
+}
+
+// A complete rule family: speed, locking, delays, rotation, scoring and
+// leveling. `Game` asks its rule set instead of hardcoding any of them.
+pub trait RuleSet {
+    fn name(&self) -> &str;
+
+    // Rows per frame at 60 frames per second; 20.0 (20G) drops a piece to the
+    // floor as soon as it appears
+    fn gravity(&self, level: u32) -> f32;
+
+    fn lock_delay(&self, level: u32) -> f32;
+
+    fn lock_reset(&self) -> LockReset;
+
+    fn delays(&self) -> Delays;
+
+    fn handling(&self) -> Handling;
+
+    fn rotation(&self) -> RotationSystem;
+
+    fn hold_enabled(&self) -> bool {
+        true
+    }
+
+    fn hard_drop_enabled(&self) -> bool {
+        true
+    }
+
+    fn t_spins_enabled(&self) -> bool {
+        true
+    }
+
+    fn score(&self, state: &mut ScoreState, lines: usize, t_spin: TSpin, perfect_clear: bool, level: u32) -> ClearAction {
+        state.score_lock(lines, t_spin, perfect_clear, level)
+    }
+
+    // Level reached after clearing `lines` lines, starting from 1
+    fn level(&self, lines: u32) -> u32 {
+        lines / LINES_PER_LEVEL + 1
+    }
+}
+
+// The sets offered in the menu, in order
+pub fn builtin() -> Vec<Rc<dyn RuleSet>> {
+    vec![Rc::new(Modern), Rc::new(NesClassic), Rc::new(TgmLike)]
+}
+
+// Guideline play: SRS with 180 kicks, hold, hard drop, T-spins, back-to-back
+// and combos, move reset lock delay
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Modern;
+
+impl RuleSet for Modern {
+    fn name(&self) -> &str {
+        "MODERN"
+    }
+
+    fn gravity(&self, level: u32) -> f32 {
+        let interval = (INITIAL_DROP_INTERVAL - (level - 1) as f32 * LEVEL_SPEED_INCREASE).max(MIN_DROP_INTERVAL);
+        1.0 / (interval * FRAMES_PER_SECOND)
+    }
+
+    fn lock_delay(&self, _level: u32) -> f32 {
+        LOCK_DELAY
+    }
+
+    fn lock_reset(&self) -> LockReset {
+        LockReset::Move { max: MAX_LOCK_RESETS }
+    }
+
+    fn delays(&self) -> Delays {
+        Delays::default()
+    }
+
+    fn handling(&self) -> Handling {
+        Handling::default()
+    }
+
+    fn rotation(&self) -> RotationSystem {
+        RotationSystem::Srs
+    }
+}
+
+// Frames per row for each NES level (level 1 here is NES level 0), then 1
+const NES_FRAMES_PER_ROW: [u32; 29] = [
+    48, 43, 38, 33, 28, 23, 18, 13, 8, 6,
+    5, 5, 5, 4, 4, 4, 3, 3, 3,
+    2, 2, 2, 2, 2, 2, 2, 2, 2, 2,
+];
+
+const NES_LINE_SCORES: [u32; 5] = [0, 40, 100, 300, 1200];
+
+// NES-style: no hold, no hard drop, no kicks, the NES speed table and line
+// scores, and a piece locks on the first gravity step it cannot fall
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NesClassic;
+
+impl NesClassic {
+    fn frames_per_row(level: u32) -> u32 {
+        NES_FRAMES_PER_ROW
+            .get(level.saturating_sub(1) as usize)
+            .copied()
+            .unwrap_or(1)
+    }
+}
+
+impl RuleSet for NesClassic {
+    fn name(&self) -> &str {
+        "NES CLASSIC"
+    }
+
+    fn gravity(&self, level: u32) -> f32 {
+        1.0 / Self::frames_per_row(level) as f32
+    }
+
+    fn lock_delay(&self, level: u32) -> f32 {
+        Self::frames_per_row(level) as f32 / FRAMES_PER_SECOND
+    }
+
+    fn lock_reset(&self) -> LockReset {
+        LockReset::Step
+    }
+
+    fn delays(&self) -> Delays {
+        Delays::constant(18.0 / FRAMES_PER_SECOND, 10.0 / FRAMES_PER_SECOND)
+    }
+
+    fn handling(&self) -> Handling {
+        Handling {
+            das: 16.0 / FRAMES_PER_SECOND,
+            arr: 6.0 / FRAMES_PER_SECOND,
+            soft_drop_factor: SOFT_DROP_FACTOR,
+        }
+    }
+
+    fn rotation(&self) -> RotationSystem {
+        RotationSystem::None
+    }
+
+    fn hold_enabled(&self) -> bool {
+        false
+    }
+
+    fn hard_drop_enabled(&self) -> bool {
+        false
+    }
+
+    fn t_spins_enabled(&self) -> bool {
+        false
+    }
+
+    fn score(&self, _state: &mut ScoreState, lines: usize, _t_spin: TSpin, _perfect_clear: bool, level: u32) -> ClearAction {
+        ClearAction {
+            lines,
+            t_spin: TSpin::None,
+            back_to_back: false,
+            combo: 0,
+            perfect_clear: false,
+            chain: 0,
+            points: NES_LINE_SCORES[lines.min(4)] * level,
+        }
+    }
+}
+
+// TGM-like: 20G from the start, ARS rotation, step reset lock delay and long
+// line clear and entry delays. Scored like the guideline, without T-spins.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TgmLike;
+
+impl RuleSet for TgmLike {
+    fn name(&self) -> &str {
+        "TGM-LIKE"
+    }
+
+    fn gravity(&self, _level: u32) -> f32 {
+        20.0
+    }
+
+    fn lock_delay(&self, _level: u32) -> f32 {
+        30.0 / FRAMES_PER_SECOND
+    }
+
+    fn lock_reset(&self) -> LockReset {
+        LockReset::Step
+    }
+
+    fn delays(&self) -> Delays {
+        Delays::constant(41.0 / FRAMES_PER_SECOND, 30.0 / FRAMES_PER_SECOND)
+    }
+
+    fn handling(&self) -> Handling {
+        Handling {
+            das: 14.0 / FRAMES_PER_SECOND,
+            arr: 1.0 / FRAMES_PER_SECOND,
+            soft_drop_factor: SOFT_DROP_FACTOR,
+        }
+    }
+
+    fn rotation(&self) -> RotationSystem {
+        RotationSystem::Ars
+    }
+
+    fn t_spins_enabled(&self) -> bool {
+        false
+    }
+}
+
+// Gravity in G from each level on: doubling up to 1G, then on to 20G
+const MASTER_GRAVITY: [(u32, f32); 11] = [
+    (1, 1.0 / 64.0),
+    (2, 1.0 / 32.0),
+    (3, 1.0 / 16.0),
+    (4, 1.0 / 8.0),
+    (5, 1.0 / 4.0),
+    (6, 1.0 / 2.0),
+    (7, 1.0),
+    (8, 2.0),
+    (9, 3.0),
+    (10, 5.0),
+    (11, INSTANT_GRAVITY),
+];
+
+// Frames of lock delay, line clear delay and ARE from each level on; they
+// shrink once gravity reaches 20G
+const MASTER_TIMING: [(u32, f32, f32, f32); 6] = [
+    (1, 30.0, 40.0, 25.0),
+    (11, 30.0, 25.0, 25.0),
+    (13, 26.0, 16.0, 16.0),
+    (15, 22.0, 12.0, 12.0),
+    (17, 18.0, 6.0, 6.0),
+    (19, 15.0, 6.0, 6.0),
+];
+
+// Master mode speed: gravity ramps up to 20G by level 11, then lock delay,
+// line clear delay and ARE get shorter every two levels. Lock delay only
+// resets on reaching a new row. Everything else comes from the rules it
+// wraps.
+pub struct Master {
+    name: String,
+    base: Rc<dyn RuleSet>,
+}
+
+impl Master {
+    pub fn new(base: Rc<dyn RuleSet>) -> Self {
+        Master {
+            name: format!("MASTER ({})", base.name()),
+            base,
+        }
+    }
+}
+
+impl RuleSet for Master {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn gravity(&self, level: u32) -> f32 {
+        MASTER_GRAVITY
+            .iter()
+            .rev()
+            .find(|&&(from_level, _)| from_level <= level)
+            .map_or(MASTER_GRAVITY[0].1, |&(_, gravity)| gravity)
+    }
+
+    fn lock_delay(&self, level: u32) -> f32 {
+        let frames = MASTER_TIMING
+            .iter()
+            .rev()
+            .find(|&&(from_level, ..)| from_level <= level)
+            .map_or(MASTER_TIMING[0].1, |&(_, lock, ..)| lock);
+        frames / FRAMES_PER_SECOND
+    }
+
+    fn lock_reset(&self) -> LockReset {
+        LockReset::Step
+    }
+
+    fn delays(&self) -> Delays {
+        Delays::from_steps(
+            MASTER_TIMING
+                .iter()
+                .map(|&(from_level, _, line_clear, entry)| DelayStep {
+                    from_level,
+                    line_clear: line_clear / FRAMES_PER_SECOND,
+                    entry: entry / FRAMES_PER_SECOND,
+                })
+                .collect(),
+        )
+    }
+
+    fn handling(&self) -> Handling {
+        self.base.handling()
+    }
+
+    fn rotation(&self) -> RotationSystem {
+        self.base.rotation()
+    }
+
+    fn hold_enabled(&self) -> bool {
+        self.base.hold_enabled()
+    }
+
+    fn hard_drop_enabled(&self) -> bool {
+        self.base.hard_drop_enabled()
+    }
+
+    fn t_spins_enabled(&self) -> bool {
+        self.base.t_spins_enabled()
+    }
+
+    fn score(&self, state: &mut ScoreState, lines: usize, t_spin: TSpin, perfect_clear: bool, level: u32) -> ClearAction {
+        self.base.score(state, lines, t_spin, perfect_clear, level)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleFamily {
+    Modern,
+    Nes,
+    Tgm,
+}
+
+impl RuleFamily {
+    pub fn create(self) -> Box<dyn RuleSet> {
+        match self {
+            RuleFamily::Modern => Box::new(Modern),
+            RuleFamily::Nes => Box::new(NesClassic),
+            RuleFamily::Tgm => Box::new(TgmLike),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum RulesError {
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+}
+
+impl fmt::Display for RulesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RulesError::Io(error) => write!(f, "could not read rules: {}", error),
+            RulesError::Parse(error) => write!(f, "could not parse rules: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for RulesError {}
+
+// A rules file: a base family plus any values to change, e.g.
+// { "name": "NES WITH HOLD", "base": "nes", "hold": true }
+#[derive(Debug, Clone, Deserialize)]
+struct RulesFile {
+    name: String,
+    base: RuleFamily,
+    gravity: Option<f32>,
+    lock_delay: Option<f32>,
+    lock_resets: Option<u32>,
+    line_clear_delay: Option<f32>,
+    entry_delay: Option<f32>,
+    das: Option<f32>,
+    arr: Option<f32>,
+    soft_drop_factor: Option<f32>,
+    rotation: Option<RotationSystem>,
+    hold: Option<bool>,
+    hard_drop: Option<bool>,
+    t_spins: Option<bool>,
+}
+
+// Rules loaded from a file. Anything the file leaves out comes from its base.
+pub struct CustomRules {
+    file: RulesFile,
+    base: Box<dyn RuleSet>,
+}
+
+impl CustomRules {
+    pub fn load(path: &Path) -> Result<Self, RulesError> {
+        let json = std::fs::read_to_string(path).map_err(RulesError::Io)?;
+        Self::from_json(&json)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, RulesError> {
+        let file: RulesFile = serde_json::from_str(json).map_err(RulesError::Parse)?;
+        let base = file.base.create();
+        Ok(CustomRules { file, base })
+    }
+}
+
+impl RuleSet for CustomRules {
+    fn name(&self) -> &str {
+        &self.file.name
+    }
+
+    fn gravity(&self, level: u32) -> f32 {
+        self.file.gravity.unwrap_or_else(|| self.base.gravity(level))
+    }
+
+    fn lock_delay(&self, level: u32) -> f32 {
+        self.file.lock_delay.unwrap_or_else(|| self.base.lock_delay(level))
+    }
+
+    fn lock_reset(&self) -> LockReset {
+        match self.file.lock_resets {
+            Some(max) => LockReset::Move { max },
+            None => self.base.lock_reset(),
+        }
+    }
+
+    fn delays(&self) -> Delays {
+        let base = self.base.delays();
+        match (self.file.line_clear_delay, self.file.entry_delay) {
+            (None, None) => base,
+            (line_clear, entry) => Delays::constant(
+                line_clear.unwrap_or_else(|| base.line_clear(1)),
+                entry.unwrap_or_else(|| base.entry(1)),
+            ),
+        }
+    }
+
+    fn handling(&self) -> Handling {
+        let base = self.base.handling();
+        Handling {
+            das: self.file.das.unwrap_or(base.das),
+            arr: self.file.arr.unwrap_or(base.arr),
+            soft_drop_factor: self.file.soft_drop_factor.unwrap_or(base.soft_drop_factor),
+        }
+    }
+
+    fn rotation(&self) -> RotationSystem {
+        self.file.rotation.unwrap_or_else(|| self.base.rotation())
+    }
+
+    fn hold_enabled(&self) -> bool {
+        self.file.hold.unwrap_or_else(|| self.base.hold_enabled())
+    }
+
+    fn hard_drop_enabled(&self) -> bool {
+        self.file.hard_drop.unwrap_or_else(|| self.base.hard_drop_enabled())
+    }
+
+    fn t_spins_enabled(&self) -> bool {
+        self.file.t_spins.unwrap_or_else(|| self.base.t_spins_enabled())
+    }
+
+    fn score(&self, state: &mut ScoreState, lines: usize, t_spin: TSpin, perfect_clear: bool, level: u32) -> ClearAction {
+        self.base.score(state, lines, t_spin, perfect_clear, level)
+    }
+
+    fn level(&self, lines: u32) -> u32 {
+        self.base.level(lines)
+    }
+}
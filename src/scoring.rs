@@ -0,0 +1,207 @@
+use crate::config::{
+    SCORE_SINGLE_LINE, SCORE_DOUBLE_LINE, SCORE_TRIPLE_LINE, SCORE_TETRIS,
+    SCORE_TSPIN_MINI, SCORE_TSPIN_MINI_SINGLE, SCORE_TSPIN_MINI_DOUBLE,
+    SCORE_TSPIN, SCORE_TSPIN_SINGLE, SCORE_TSPIN_DOUBLE, SCORE_TSPIN_TRIPLE,
+    SCORE_COMBO, SCORE_PERFECT_CLEAR,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TSpin {
+    None,
+    Mini,
+    Full,
+}
+
+// Everything that went into scoring one locked piece
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClearAction {
+    pub lines: usize,
+    pub t_spin: TSpin,
+    pub back_to_back: bool,
+    // Consecutive line-clearing pieces, 0 for the first one
+    pub combo: u32,
+    pub perfect_clear: bool,
+    // Step of a cascade chain the clear came from, 0 when the piece itself
+    // cleared the rows
+    pub chain: u32,
+    pub points: u32,
+}
+
+impl ClearAction {
+    // True when the action is worth showing to the player
+    pub fn is_notable(&self) -> bool {
+        self.lines > 0 || self.t_spin != TSpin::None
+    }
+
+    // HUD text, e.g. "B2B T-SPIN DOUBLE", "DESTRUCTION", "3 COMBO"
+    pub fn name(&self) -> String {
+        let mut parts = Vec::new();
+        if self.back_to_back {
+            parts.push("B2B".to_string());
+        }
+        match self.t_spin {
+            TSpin::Mini => parts.push("T-SPIN MINI".to_string()),
+            TSpin::Full => parts.push("T-SPIN".to_string()),
+            TSpin::None => {}
+        }
+        let lines = match self.lines {
+            0 => None,
+            1 => Some("SINGLE"),
+            2 => Some("DOUBLE"),
+            3 => Some("TRIPLE"),
+            _ => Some("DESTRUCTION"),
+        };
+        if let Some(lines) = lines {
+            parts.push(lines.to_string());
+        }
+        if self.combo > 0 {
+            parts.push(format!("{} COMBO", self.combo));
+        }
+        if self.perfect_clear {
+            parts.push("PERFECT CLEAR".to_string());
+        }
+        if self.chain > 0 {
+            parts.push(format!("CHAIN x{}", self.chain + 1));
+        }
+        parts.join(" ")
+    }
+}
+
+// Guideline scoring: line clears, T-spins, back-to-back chains, combos and perfect clears
+#[derive(Debug, Clone)]
+pub struct ScoreState {
+    // Whether the last line clear was a "difficult" one (4+ lines or a T-spin)
+    last_clear_difficult: bool,
+    // -1 when no combo is running
+    combo: i32,
+}
+
+impl Default for ScoreState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScoreState {
+    pub fn new() -> Self {
+        ScoreState {
+            last_clear_difficult: false,
+            combo: -1,
+        }
+    }
+
+    // Score a locked piece. `level` is the level before any level-up it causes.
+    pub fn score_lock(&mut self, lines: usize, t_spin: TSpin, perfect_clear: bool, level: u32) -> ClearAction {
+        let base = match (t_spin, lines) {
+            (TSpin::None, 0) => 0,
+            (TSpin::None, 1) => SCORE_SINGLE_LINE,
+            (TSpin::None, 2) => SCORE_DOUBLE_LINE,
+            (TSpin::None, 3) => SCORE_TRIPLE_LINE,
+            (TSpin::None, _) => SCORE_TETRIS,
+            (TSpin::Mini, 0) => SCORE_TSPIN_MINI,
+            (TSpin::Mini, 1) => SCORE_TSPIN_MINI_SINGLE,
+            (TSpin::Mini, _) => SCORE_TSPIN_MINI_DOUBLE,
+            (TSpin::Full, 0) => SCORE_TSPIN,
+            (TSpin::Full, 1) => SCORE_TSPIN_SINGLE,
+            (TSpin::Full, 2) => SCORE_TSPIN_DOUBLE,
+            (TSpin::Full, _) => SCORE_TSPIN_TRIPLE,
+        };
+
+        let mut back_to_back = false;
+        let mut points = base;
+        if lines > 0 {
+            let difficult = lines >= 4 || t_spin != TSpin::None;
+            back_to_back = difficult && self.last_clear_difficult;
+            if back_to_back {
+                points = points * 3 / 2;
+            }
+            self.last_clear_difficult = difficult;
+            self.combo += 1;
+        } else {
+            // A T-spin without lines keeps the back-to-back chain alive
+            self.combo = -1;
+        }
+
+        let combo = self.combo.max(0) as u32;
+        points += SCORE_COMBO * combo;
+
+        if perfect_clear {
+            let index = lines.clamp(1, 4) - 1;
+            points += if back_to_back && lines >= 4 {
+                SCORE_PERFECT_CLEAR[4]
+            } else {
+                SCORE_PERFECT_CLEAR[index]
+            };
+        }
+
+        ClearAction {
+            lines,
+            t_spin,
+            back_to_back,
+            combo,
+            perfect_clear,
+            chain: 0,
+            points: points * level,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scores_line_clears_times_level() {
+        let mut state = ScoreState::new();
+        assert_eq!(state.score_lock(1, TSpin::None, false, 1).points, 100);
+        let mut state = ScoreState::new();
+        assert_eq!(state.score_lock(4, TSpin::None, false, 3).points, 2400);
+    }
+
+    #[test]
+    fn counts_combos_until_a_piece_clears_nothing() {
+        let mut state = ScoreState::new();
+        let points: Vec<u32> = (0..3).map(|_| state.score_lock(1, TSpin::None, false, 1).points).collect();
+        assert_eq!(points, [100, 150, 200]);
+        assert_eq!(state.score_lock(0, TSpin::None, false, 1).points, 0);
+        assert_eq!(state.score_lock(1, TSpin::None, false, 1).combo, 0);
+    }
+
+    #[test]
+    fn back_to_back_needs_consecutive_difficult_clears() {
+        let mut state = ScoreState::new();
+        state.score_lock(4, TSpin::None, false, 1);
+        // A T-spin without lines keeps the chain
+        state.score_lock(0, TSpin::Full, false, 1);
+        let action = state.score_lock(2, TSpin::Full, false, 1);
+        assert!(action.back_to_back);
+        assert_eq!(action.points, 1800);
+        assert_eq!(action.name(), "B2B T-SPIN DOUBLE");
+
+        // A plain double breaks it
+        state.score_lock(2, TSpin::None, false, 1);
+        assert!(!state.score_lock(4, TSpin::None, false, 1).back_to_back);
+    }
+
+    #[test]
+    fn adds_perfect_clear_bonuses() {
+        let mut state = ScoreState::new();
+        let action = state.score_lock(1, TSpin::None, false, 1);
+        assert!(!action.perfect_clear);
+        let mut state = ScoreState::new();
+        assert_eq!(state.score_lock(1, TSpin::None, true, 1).points, 900);
+        let mut state = ScoreState::new();
+        state.score_lock(4, TSpin::None, false, 1);
+        state.score_lock(0, TSpin::None, false, 1);
+        // Back-to-back Destruction: 800 * 1.5 + 3200
+        assert_eq!(state.score_lock(4, TSpin::None, true, 1).points, 4400);
+    }
+
+    #[test]
+    fn names_actions() {
+        let mut state = ScoreState::new();
+        assert_eq!(state.score_lock(0, TSpin::Mini, false, 1).name(), "T-SPIN MINI");
+        state.score_lock(1, TSpin::None, false, 1);
+        assert_eq!(state.score_lock(4, TSpin::None, false, 1).name(), "DESTRUCTION 1 COMBO");
+    }
+}
@@ -0,0 +1,55 @@
+use crate::config::{LINE_CLEAR_DELAY, ENTRY_DELAY};
+
+// Delays that apply from a given level on, in seconds
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DelayStep {
+    pub from_level: u32,
+    pub line_clear: f32,
+    pub entry: f32,
+}
+
+// Line clear delay and entry delay (ARE) per level. Each step holds until the
+// next one starts; levels below the first step use the first step.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Delays {
+    steps: Vec<DelayStep>,
+}
+
+impl Default for Delays {
+    fn default() -> Self {
+        Self::constant(LINE_CLEAR_DELAY, ENTRY_DELAY)
+    }
+}
+
+impl Delays {
+    pub fn constant(line_clear: f32, entry: f32) -> Self {
+        Delays {
+            steps: vec![DelayStep { from_level: 1, line_clear, entry }],
+        }
+    }
+
+    // Steps may be given in any order
+    pub fn from_steps(mut steps: Vec<DelayStep>) -> Self {
+        if steps.is_empty() {
+            return Self::default();
+        }
+        steps.sort_by_key(|step| step.from_level);
+        Delays { steps }
+    }
+
+    pub fn at_level(&self, level: u32) -> DelayStep {
+        *self.steps
+            .iter()
+            .rev()
+            .find(|step| step.from_level <= level)
+            .unwrap_or(&self.steps[0])
+    }
+
+    pub fn line_clear(&self, level: u32) -> f32 {
+        self.at_level(level).line_clear
+    }
+
+    pub fn entry(&self, level: u32) -> f32 {
+        self.at_level(level).entry
+    }
+}
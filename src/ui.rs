@@ -2,46 +2,136 @@ use ggez::{Context, GameResult};
 use ggez::graphics::{self, Color, DrawMode, DrawParam, Mesh, Rect, Text, TextFragment};
 use ggez::mint::Point2;
 
-use crate::game::{Game, GameState};
-use crate::tetromino::Tetromino;
-use crate::config::{MENU_TEXT_COLOR, MENU_HIGHLIGHT_COLOR, BORDER_COLOR};
+use purple_box_destruction::game::{Game, GameState, TopOut};
+use purple_box_destruction::tetromino::Tetromino;
+use purple_box_destruction::config::{MENU_TEXT_COLOR, MENU_HIGHLIGHT_COLOR, MENU_BACKGROUND_COLOR, BORDER_COLOR, TIME_WARNING};
+use purple_box_destruction::mode::GameMode;
 
-pub struct UI {}
+use crate::render::{draw_tetromino, BoardRenderer};
+
+// How long a scoring action stays on the HUD, in seconds
+const ACTION_DISPLAY_TIME: f32 = 2.0;
+
+pub struct UI {
+    action_text: Option<String>,
+    action_timer: f32,
+    // Best time or score for the current mode and piece set, already
+    // formatted, shown on the results screen
+    personal_best: Option<String>,
+    new_record: bool,
+}
 
 impl UI {
     pub fn new(_ctx: &mut Context) -> GameResult<UI> {
-        Ok(UI {})
+        Ok(UI {
+            action_text: None,
+            action_timer: 0.0,
+            personal_best: None,
+            new_record: false,
+        })
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        if self.action_text.is_some() {
+            self.action_timer -= dt;
+            if self.action_timer <= 0.0 {
+                self.action_text = None;
+            }
+        }
     }
 
-    pub fn draw(&self, ctx: &mut Context, canvas: &mut graphics::Canvas, game: &Game) -> GameResult {
+    // Show a scoring action such as "T-SPIN DOUBLE" for a moment
+    pub fn show_action(&mut self, text: String) {
+        self.action_text = Some(text);
+        self.action_timer = ACTION_DISPLAY_TIME;
+    }
+
+    pub fn set_personal_best(&mut self, best: Option<String>, new_record: bool) {
+        self.personal_best = best;
+        self.new_record = new_record;
+    }
+
+    pub fn draw(&self, ctx: &mut Context, canvas: &mut graphics::Canvas, game: &Game, renderer: &BoardRenderer) -> GameResult {
         let text_color = Color::new(MENU_TEXT_COLOR.0, MENU_TEXT_COLOR.1, MENU_TEXT_COLOR.2, MENU_TEXT_COLOR.3);
+        let highlight_color = Color::new(MENU_HIGHLIGHT_COLOR.0, MENU_HIGHLIGHT_COLOR.1, MENU_HIGHLIGHT_COLOR.2, MENU_HIGHLIGHT_COLOR.3);
+        
+        // Draw score, or the timer in modes played against the clock
+        if game.mode.is_race() {
+            self.draw_text(canvas, &format!("Time: {}", format_time(game.elapsed)), 550.0, 50.0, 24.0, text_color)?;
+        } else {
+            self.draw_text(canvas, &format!("Score: {}", game.get_score()), 550.0, 50.0, 24.0, text_color)?;
+        }
+        
+        // Draw the time left, blinking in the last seconds
+        if let Some(remaining) = game.remaining_time() {
+            let warning = remaining <= TIME_WARNING && game.get_state() == &GameState::Playing;
+            let visible = !warning || ((remaining * 4.0) as u32).is_multiple_of(2);
+            if visible {
+                let color = if warning { highlight_color } else { text_color };
+                self.draw_text(canvas, &format!("Time: {}", format_time(remaining)), 550.0, 20.0, 24.0, color)?;
+            }
+        }
         
-        // Draw score
-        self.draw_text(canvas, &format!("Score: {}", game.get_score()), 550.0, 50.0, 24.0, text_color)?;
+        // Draw the puzzle's objective
+        if game.mode == GameMode::Puzzle {
+            if let Some(ref puzzle) = game.puzzle {
+                self.draw_text(canvas, &puzzle.objective.description(), 550.0, 24.0, 14.0, highlight_color)?;
+            }
+        }
         
         // Draw level
         self.draw_text(canvas, &format!("Level: {}", game.get_level()), 550.0, 80.0, 24.0, text_color)?;
         
-        // Draw lines cleared
-        self.draw_text(canvas, &format!("Lines: {}", game.get_lines_cleared()), 550.0, 110.0, 24.0, text_color)?;
+        // Draw lines cleared, out of the goal if there is one
+        let lines = match game.mode.line_goal() {
+            Some(goal) => format!("Lines: {}/{}", game.get_lines_cleared().min(goal), goal),
+            None if game.mode.has_garbage() => format!("Garbage: {}", game.garbage_left()),
+            None => format!("Lines: {}", game.get_lines_cleared()),
+        };
+        self.draw_text(canvas, &lines, 550.0, 110.0, 24.0, text_color)?;
         
-        // Draw next piece preview
-        self.draw_text(canvas, "Next:", 550.0, 150.0, 20.0, text_color)?;
-        self.draw_next_piece(ctx, canvas, game.get_next_tetromino(), 550.0, 180.0)?;
+        // Draw next queue in a column right of the board
+        let queue_x = renderer.right_edge() + 20.0;
+        self.draw_text(canvas, "Next:", queue_x, renderer.offset_y, 20.0, text_color)?;
+        self.draw_next_queue(ctx, canvas, game, queue_x, renderer.offset_y + 30.0)?;
+        
+        // Draw hold slot
+        self.draw_text(canvas, "Hold:", 550.0, 150.0, 20.0, text_color)?;
+        self.draw_piece_preview(ctx, canvas, game, 550.0, 180.0)?;
+        
+        // Draw seed so a game can be replayed
+        self.draw_text(canvas, &format!("Seed: {}", game.get_seed()), 550.0, 270.0, 16.0, text_color)?;
+        self.draw_text(canvas, &format!("Randomizer: {}", game.get_randomizer_kind().name()), 550.0, 290.0, 16.0, text_color)?;
         
         // Draw controls
         self.draw_controls(canvas)?;
         
+        // Draw the last scoring action, fading out
+        if let Some(ref text) = self.action_text {
+            let mut color = highlight_color;
+            color.a = (self.action_timer / ACTION_DISPLAY_TIME).min(1.0);
+            self.draw_text(canvas, text, 550.0, 398.0, 18.0, color)?;
+        }
+        
         // Draw game state messages
-        let highlight_color = Color::new(MENU_HIGHLIGHT_COLOR.0, MENU_HIGHLIGHT_COLOR.1, MENU_HIGHLIGHT_COLOR.2, MENU_HIGHLIGHT_COLOR.3);
         match game.get_state() {
             GameState::Paused => {
-                self.draw_text(canvas, "PAUSED", 550.0, 300.0, 32.0, highlight_color)?;
-                self.draw_text(canvas, "Press P to resume", 550.0, 340.0, 16.0, text_color)?;
+                self.draw_text(canvas, "PAUSED", 550.0, 320.0, 32.0, highlight_color)?;
+                self.draw_text(canvas, "Press P to resume", 550.0, 360.0, 16.0, text_color)?;
             }
             GameState::GameOver => {
-                self.draw_text(canvas, "GAME OVER", 550.0, 300.0, 32.0, highlight_color)?;
-                self.draw_text(canvas, "Press R to restart", 550.0, 340.0, 16.0, text_color)?;
+                self.draw_text(canvas, "GAME OVER", 550.0, 320.0, 32.0, highlight_color)?;
+                let reason = match game.top_out {
+                    Some(TopOut::BlockOut) => "Block out",
+                    Some(TopOut::LockOut) => "Lock out",
+                    None if game.mode == GameMode::Puzzle => "Objective failed",
+                    None => "",
+                };
+                self.draw_text(canvas, reason, 550.0, 378.0, 14.0, text_color)?;
+                self.draw_text(canvas, "Press R to restart", 550.0, 360.0, 16.0, text_color)?;
+            }
+            GameState::Finished => {
+                self.draw_results(ctx, canvas, game)?;
             }
             GameState::Playing => {}
             GameState::Menu => {}
@@ -50,6 +140,65 @@ impl UI {
         Ok(())
     }
 
+    // Final time and splits, or final score, and the personal best over the board
+    fn draw_results(&self, ctx: &mut Context, canvas: &mut graphics::Canvas, game: &Game) -> GameResult {
+        let text_color = Color::new(MENU_TEXT_COLOR.0, MENU_TEXT_COLOR.1, MENU_TEXT_COLOR.2, MENU_TEXT_COLOR.3);
+        let highlight_color = Color::new(MENU_HIGHLIGHT_COLOR.0, MENU_HIGHLIGHT_COLOR.1, MENU_HIGHLIGHT_COLOR.2, MENU_HIGHLIGHT_COLOR.3);
+        let background_color = Color::new(MENU_BACKGROUND_COLOR.0, MENU_BACKGROUND_COLOR.1, MENU_BACKGROUND_COLOR.2, 0.95);
+        let border_color = Color::new(BORDER_COLOR.0, BORDER_COLOR.1, BORDER_COLOR.2, BORDER_COLOR.3);
+
+        let panel = Rect::new(200.0, 150.0, 400.0, 300.0);
+        let background_mesh = Mesh::new_rectangle(ctx, DrawMode::fill(), panel, background_color)?;
+        canvas.draw(&background_mesh, DrawParam::default());
+        let border_mesh = Mesh::new_rectangle(ctx, DrawMode::stroke(2.0), panel, border_color)?;
+        canvas.draw(&border_mesh, DrawParam::default());
+
+        let x = panel.x + 30.0;
+        self.draw_text(canvas, game.mode.name(), x, panel.y + 20.0, 28.0, highlight_color)?;
+        match game.mode {
+            GameMode::Puzzle => {
+                let name = game.puzzle.as_ref().map_or("", |puzzle| puzzle.name.as_str());
+                self.draw_text(canvas, name, x, panel.y + 60.0, 24.0, text_color)?;
+                self.draw_text(canvas, &format!("Pieces: {}", game.pieces_placed), x, panel.y + 100.0, 18.0, text_color)?;
+                self.draw_text(canvas, "SOLVED", x, panel.y + 200.0, 24.0, highlight_color)?;
+                self.draw_text(canvas, "Press R to retry, ESC for menu", x, panel.y + 250.0, 16.0, text_color)?;
+                return Ok(());
+            }
+            GameMode::Ultra => {
+                self.draw_text(canvas, &format!("Score: {}", game.get_score()), x, panel.y + 60.0, 24.0, text_color)?;
+                let summary = [
+                    format!("Time: {}", format_time(game.time_limit)),
+                    format!("Lines: {}", game.get_lines_cleared()),
+                    format!("Level: {}", game.get_level()),
+                ];
+                for (i, line) in summary.iter().enumerate() {
+                    self.draw_text(canvas, line, x, panel.y + 100.0 + i as f32 * 22.0, 18.0, text_color)?;
+                }
+            }
+            _ => {
+                self.draw_text(canvas, &format!("Time: {}", format_time(game.elapsed)), x, panel.y + 60.0, 24.0, text_color)?;
+                let split_lines = game.mode.split_lines().unwrap_or(0);
+                for (i, split) in game.splits.iter().enumerate() {
+                    let lines = (i as u32 + 1) * split_lines;
+                    let y = panel.y + 100.0 + i as f32 * 22.0;
+                    self.draw_text(canvas, &format!("{:>3} lines  {}", lines, format_time(*split)), x, y, 18.0, text_color)?;
+                }
+                if game.mode.has_garbage() {
+                    self.draw_text(canvas, &format!("Garbage rows: {}", game.garbage_rows), x, panel.y + 100.0, 18.0, text_color)?;
+                }
+            }
+        }
+
+        if self.new_record {
+            self.draw_text(canvas, "NEW RECORD", x, panel.y + 200.0, 24.0, highlight_color)?;
+        } else if let Some(ref best) = self.personal_best {
+            self.draw_text(canvas, &format!("Best: {}", best), x, panel.y + 200.0, 20.0, text_color)?;
+        }
+        self.draw_text(canvas, "Press R to retry, ESC for menu", x, panel.y + 250.0, 16.0, text_color)?;
+
+        Ok(())
+    }
+
     fn draw_text(&self, canvas: &mut graphics::Canvas, text: &str, x: f32, y: f32, size: f32, color: Color) -> GameResult {
         let text_fragment = TextFragment::new(text)
             .scale(size)
@@ -62,29 +211,51 @@ impl UI {
         Ok(())
     }
 
-    fn draw_next_piece(&self, ctx: &mut Context, canvas: &mut graphics::Canvas, tetromino: &Tetromino, x: f32, y: f32) -> GameResult {
-        let block_size = 20.0;
-        let offset_x = x + 20.0;
-        let offset_y = y + 20.0;
-        
-        // Draw background for next piece preview
+    fn draw_piece_preview(&self, ctx: &mut Context, canvas: &mut graphics::Canvas, game: &Game, x: f32, y: f32) -> GameResult {
+        // Draw background for the preview box
         let preview_rect = Rect::new(x, y, 100.0, 80.0);
         let border_color = Color::new(BORDER_COLOR.0, BORDER_COLOR.1, BORDER_COLOR.2, BORDER_COLOR.3);
         let preview_mesh = Mesh::new_rectangle(ctx, DrawMode::stroke(2.0), preview_rect, border_color)?;
         canvas.draw(&preview_mesh, DrawParam::default());
         
-        // Draw the next tetromino
-        tetromino.draw(ctx, canvas, block_size, offset_x, offset_y)?;
+        if let Some(tetromino) = game.get_hold_tetromino() {
+            let slot = Rect::new(x + 10.0, y + 10.0, 80.0, 60.0);
+            let block_size = preview_block_size(game, slot, 20.0);
+            draw_centered(ctx, canvas, tetromino, block_size, slot)?;
+        }
+        
+        Ok(())
+    }
+
+    fn draw_next_queue(&self, ctx: &mut Context, canvas: &mut graphics::Canvas, game: &Game, x: f32, y: f32) -> GameResult {
+        let slot_size = 48.0;
+        let count = game.get_next_queue().count();
+        if count == 0 {
+            return Ok(());
+        }
+        
+        // One box around the whole column
+        let column_rect = Rect::new(x, y, slot_size + 16.0, slot_size * count as f32 + 16.0);
+        let border_color = Color::new(BORDER_COLOR.0, BORDER_COLOR.1, BORDER_COLOR.2, BORDER_COLOR.3);
+        let column_mesh = Mesh::new_rectangle(ctx, DrawMode::stroke(2.0), column_rect, border_color)?;
+        canvas.draw(&column_mesh, DrawParam::default());
+        
+        let block_size = preview_block_size(game, Rect::new(0.0, 0.0, slot_size, slot_size), 12.0);
+        for (i, tetromino) in game.get_next_queue().enumerate() {
+            let slot = Rect::new(x + 8.0, y + 8.0 + i as f32 * slot_size, slot_size, slot_size);
+            draw_centered(ctx, canvas, tetromino, block_size, slot)?;
+        }
         
         Ok(())
     }
 
     fn draw_controls(&self, canvas: &mut graphics::Canvas) -> GameResult {
-        let controls = vec![
+        let controls = [
             "Controls:",
             "Arrow Keys - Move",
-            "Up - Rotate",
+            "Up/X - Rotate  Z - Left  A - 180",
             "Space - Hard Drop",
+            "C/Shift - Hold",
             "P - Pause",
             "R - Restart",
             "ESC - Menu",
@@ -94,7 +265,7 @@ impl UI {
         let highlight_color = Color::new(MENU_HIGHLIGHT_COLOR.0, MENU_HIGHLIGHT_COLOR.1, MENU_HIGHLIGHT_COLOR.2, MENU_HIGHLIGHT_COLOR.3);
         
         for (i, control) in controls.iter().enumerate() {
-            let y = 450.0 + (i as f32 * 20.0);
+            let y = 420.0 + (i as f32 * 20.0);
             let color = if i == 0 { highlight_color } else { text_color };
             let size = if i == 0 { 18.0 } else { 14.0 };
             
@@ -104,3 +275,44 @@ impl UI {
         Ok(())
     }
 }
+
+// First column, first row, width and height of the blocks in a piece matrix
+fn piece_bounds(blocks: &[Vec<bool>]) -> (i32, i32, i32, i32) {
+    let cells: Vec<(i32, i32)> = blocks
+        .iter()
+        .enumerate()
+        .flat_map(|(y, row)| row.iter().enumerate().filter(|(_, &block)| block).map(move |(x, _)| (x as i32, y as i32)))
+        .collect();
+    let left = cells.iter().map(|&(x, _)| x).min().unwrap_or(0);
+    let top = cells.iter().map(|&(_, y)| y).min().unwrap_or(0);
+    let right = cells.iter().map(|&(x, _)| x).max().unwrap_or(0);
+    let bottom = cells.iter().map(|&(_, y)| y).max().unwrap_or(0);
+    (left, top, right - left + 1, bottom - top + 1)
+}
+
+// Block size that fits the widest and tallest spawn state of the set in
+// `slot`, at most `max_size`, so every preview of a game has the same scale
+fn preview_block_size(game: &Game, slot: Rect, max_size: f32) -> f32 {
+    let (width, height) = game.piece_set.pieces.iter().fold((1, 1), |(width, height), piece| {
+        let (_, _, piece_width, piece_height) = piece_bounds(piece.blocks(0));
+        (width.max(piece_width), height.max(piece_height))
+    });
+    max_size.min(slot.w / width as f32).min(slot.h / height as f32)
+}
+
+// Draws a piece centered in `slot`, whatever its position on the board
+fn draw_centered(ctx: &mut Context, canvas: &mut graphics::Canvas, tetromino: &Tetromino, block_size: f32, slot: Rect) -> GameResult {
+    let (left, top, width, height) = piece_bounds(&tetromino.blocks);
+    let mut preview = tetromino.clone();
+    preview.x = -left;
+    preview.y = -top;
+    let offset_x = slot.x + (slot.w - width as f32 * block_size) / 2.0;
+    let offset_y = slot.y + (slot.h - height as f32 * block_size) / 2.0;
+    draw_tetromino(ctx, canvas, &preview, block_size, offset_x, offset_y)
+}
+
+// Minutes, seconds and milliseconds, e.g. "1:05.250"
+pub fn format_time(seconds: f32) -> String {
+    let millis = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!("{}:{:02}.{:03}", millis / 60_000, millis / 1000 % 60, millis % 1000)
+}